@@ -0,0 +1,52 @@
+//! Benchmarks for [ImageFrontier] placement, to tune [REBUILD_RATIO].
+
+use kd_forest::color::source::AllColors;
+use kd_forest::color::{order, LabSpace};
+use kd_forest::frontier::image::{ImageFrontier, REBUILD_RATIO};
+use kd_forest::frontier::Frontier;
+
+use criterion::{criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion, Throughput};
+
+use image::{Rgba, RgbaImage};
+
+/// Time how fast an [ImageFrontier] places colors, for different rebuild ratios.
+fn place(c: &mut Criterion) {
+    // A 128x128 gradient target, painted with all 2^14 14-bit colors
+    let target = RgbaImage::from_fn(128, 128, |x, y| Rgba([2 * x as u8, 2 * y as u8, 128, 255]));
+    let colors = order::hue_sorted(AllColors::new(5, 5, 4));
+
+    let mut group = c.benchmark_group("ImageFrontier::place");
+    group.sample_size(10);
+    group.throughput(Throughput::Elements(colors.len() as u64));
+
+    for denom in [8, 16, 32, 64] {
+        let ratio = 1.0 / denom as f64;
+        let id = if ratio == REBUILD_RATIO {
+            format!("1/{} (default)", denom)
+        } else {
+            format!("1/{}", denom)
+        };
+
+        group.bench_with_input(BenchmarkId::new("rebuild_ratio", id), &ratio, |b, &ratio| {
+            b.iter_batched(
+                || {
+                    let mut frontier = ImageFrontier::<LabSpace>::new(&target);
+                    frontier.set_rebuild_ratio(Some(ratio));
+                    frontier
+                },
+                |mut frontier| {
+                    for &color in &colors {
+                        frontier.place(color);
+                    }
+                    frontier
+                },
+                BatchSize::LargeInput,
+            );
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, place);
+criterion_main!(benches);
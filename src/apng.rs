@@ -0,0 +1,61 @@
+//! [Animated PNG](https://en.wikipedia.org/wiki/APNG) output.
+
+use image::RgbaImage;
+
+use png::{BitDepth, ColorType, Encoder, EncodingError};
+
+use std::io::Write;
+
+/// Write a sequence of frames as an animated PNG, showing each one for `delay_ms` milliseconds.
+///
+/// The frames must all have the same dimensions, and there must be at least one.
+pub fn write_apng<W: Write>(
+    writer: W,
+    frames: &[RgbaImage],
+    delay_ms: u16,
+) -> Result<(), EncodingError> {
+    let (width, height) = frames.first().map_or((0, 0), |f| f.dimensions());
+
+    let mut encoder = Encoder::new(writer, width, height);
+    encoder.set_color(ColorType::Rgba);
+    encoder.set_depth(BitDepth::Eight);
+    encoder.set_animated(frames.len() as u32, 0)?;
+    encoder.set_frame_delay(delay_ms, 1000)?;
+
+    let mut writer = encoder.write_header()?;
+    for frame in frames {
+        writer.write_image_data(frame.as_raw())?;
+    }
+    writer.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use image::Rgba;
+
+    use png::Decoder;
+
+    #[test]
+    fn test_apng() {
+        let frames: Vec<_> = (0..4u8)
+            .map(|i| RgbaImage::from_pixel(3, 2, Rgba([64 * i, 255 - i, i, 255])))
+            .collect();
+
+        let mut apng = Vec::new();
+        write_apng(&mut apng, &frames, 50).unwrap();
+
+        let mut reader = Decoder::new(apng.as_slice()).read_info().unwrap();
+        let info = reader.info();
+        assert_eq!(info.size(), (3, 2));
+        assert_eq!(info.animation_control.unwrap().num_frames, 4);
+
+        let mut buf = vec![0; reader.output_buffer_size()];
+        for frame in &frames {
+            reader.next_frame(&mut buf).unwrap();
+            assert_eq!(&buf[..4], &frame.get_pixel(0, 0).0);
+            assert_eq!(buf, frame.as_raw().as_slice());
+        }
+    }
+}
@@ -1,26 +1,191 @@
 //! Colors and color spaces.
 
+pub mod convert;
 pub mod order;
 pub mod source;
 
+use self::convert::{lab_gamma, lab_inv_gamma};
+
 use acap::coords::Coordinates;
-use acap::distance::{Metric, Proximity};
+use acap::distance::{Distance, Metric, Proximity};
 use acap::euclid::{EuclideanDistance, euclidean_distance};
 
 use image::Rgb;
 
+use std::error::Error;
+use std::f64::consts::{PI, TAU};
+use std::fmt::{self, Display, Formatter};
+use std::marker::PhantomData;
 use std::ops::Index;
+use std::str::FromStr;
+use std::sync::OnceLock;
 
 /// An 8-bit RGB color.
 pub type Rgb8 = Rgb<u8>;
 
+/// Displays an [Rgb8] like `rgb(255, 128, 0)`.
+///
+/// [Rgb8] is a foreign type, so it can't implement [Display] itself.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct DisplayRgb8(pub Rgb8);
+
+impl Display for DisplayRgb8 {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let Self(Rgb([r, g, b])) = *self;
+        write!(f, "rgb({}, {}, {})", r, g, b)
+    }
+}
+
 /// A [color space](https://en.wikipedia.org/wiki/Color_space).
-pub trait ColorSpace: Copy + From<Rgb8> + Coordinates + Metric
+pub trait ColorSpace:
+    Copy
+    + Display
+    + From<Rgb8>
+    + Coordinates<Value = f64>
+    + Metric
+    + Proximity<Distance: Distance<Value = f64>>
 where
     Self::Value: PartialOrd<Self::Distance>,
 {
+    /// The name of this color space, e.g. `CIE L*a*b*`.
+    fn name() -> &'static str;
+
+    /// Compute the average of the given colors, each with the given weight.
+    fn weighted_average<I: IntoIterator<Item = (Self, f64)>>(colors: I) -> Self;
+
     /// Compute the average of the given colors.
-    fn average<I: IntoIterator<Item = Self>>(colors: I) -> Self;
+    fn average<I: IntoIterator<Item = Self>>(colors: I) -> Self {
+        Self::weighted_average(colors.into_iter().map(|c| (c, 1.0)))
+    }
+
+    /// Linearly interpolate between this color and another, from `self` at `t = 0` to `other` at
+    /// `t = 1`.
+    fn lerp(self, other: Self, t: f64) -> Self {
+        Self::weighted_average([(self, 1.0 - t), (other, t)])
+    }
+
+    /// The squared Euclidean distance between two colors, without taking a square root.
+    fn distance_squared(self, other: Self) -> f64 {
+        (0..self.dims())
+            .map(|i| {
+                let d = self.coord(i) - other.coord(i);
+                d * d
+            })
+            .sum()
+    }
+
+    /// Approximate the [geometric median](https://en.wikipedia.org/wiki/Geometric_median) of the
+    /// given colors, which is less sensitive to outliers than their average.
+    ///
+    /// This runs 10 iterations of [Weiszfeld's algorithm], starting from the average.
+    ///
+    /// [Weiszfeld's algorithm]: https://en.wikipedia.org/wiki/Geometric_median#Computation
+    fn geometric_median<I: IntoIterator<Item = Self>>(colors: I) -> Self {
+        let colors: Vec<_> = colors.into_iter().collect();
+
+        let mut median = Self::average(colors.iter().copied());
+        for _ in 0..10 {
+            median = Self::weighted_average(colors.iter().map(|c| {
+                // Avoid dividing by zero when the median lands on one of the colors
+                let distance = median.distance(c).value().max(1e-9);
+                (*c, 1.0 / distance)
+            }));
+        }
+        median
+    }
+
+    /// Convert this color back to 8-bit RGB, clamping out-of-gamut colors.
+    ///
+    /// # Panics
+    ///
+    /// The default implementation panics, for color spaces that don't support the conversion.
+    fn to_rgb8(self) -> Rgb8 {
+        panic!("Converting {} colors back to RGB is not supported", Self::name())
+    }
+
+    /// Describe an RGB color in this color space, e.g. `Lab(L=53.2, a=80.1, b=67.2)`.
+    fn to_display_string(rgb8: Rgb8) -> String {
+        Self::from(rgb8).to_string()
+    }
+}
+
+/// Error type for parsing colors from strings.
+#[derive(Debug, Eq, PartialEq)]
+pub struct ParseColorError(String);
+
+impl Display for ParseColorError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid color {:?}", self.0)
+    }
+}
+
+impl Error for ParseColorError {}
+
+/// Parse a hex color like `#FF8800`.
+///
+/// The `#` is optional, and the shorthand `#F80` means the same as `#FF8800`.
+pub fn rgb8_from_hex(s: &str) -> Result<Rgb8, ParseColorError> {
+    let err = || ParseColorError(s.to_string());
+
+    let hex = s.strip_prefix('#').unwrap_or(s);
+    let digits = hex
+        .chars()
+        .map(|c| c.to_digit(16).map(|d| d as u8))
+        .collect::<Option<Vec<_>>>()
+        .ok_or_else(err)?;
+
+    let rgb = match digits.as_slice() {
+        [r, g, b] => [17 * r, 17 * g, 17 * b],
+        [r1, r0, g1, g0, b1, b0] => [16 * r1 + r0, 16 * g1 + g0, 16 * b1 + b0],
+        _ => return Err(err()),
+    };
+    Ok(Rgb8::from(rgb))
+}
+
+/// Format color components like `Name(x=1.0, y=2.0, z=3.0)`.
+fn fmt_components(
+    f: &mut Formatter<'_>,
+    name: &str,
+    keys: [&str; 3],
+    values: [f64; 3],
+    precision: usize,
+) -> fmt::Result {
+    write!(f, "{}(", name)?;
+    for i in 0..3 {
+        if i > 0 {
+            write!(f, ", ")?;
+        }
+        write!(f, "{}={:.*}", keys[i], precision, values[i])?;
+    }
+    write!(f, ")")
+}
+
+/// Parse color components formatted by [fmt_components].
+fn parse_components(s: &str, name: &str, keys: [&str; 3]) -> Result<[f64; 3], ParseColorError> {
+    let err = || ParseColorError(s.to_string());
+
+    let args = s
+        .trim()
+        .strip_prefix(name)
+        .and_then(|s| s.strip_prefix('('))
+        .and_then(|s| s.strip_suffix(')'))
+        .ok_or_else(err)?;
+
+    let mut values = [0.0; 3];
+    let mut parts = args.split(',');
+    for (key, value) in keys.iter().zip(values.iter_mut()) {
+        let (k, v) = parts.next().and_then(|p| p.split_once('=')).ok_or_else(err)?;
+        if k.trim() != *key {
+            return Err(err());
+        }
+        *value = v.trim().parse().map_err(|_| err())?;
+    }
+
+    if parts.next().is_some() {
+        return Err(err());
+    }
+
+    Ok(values)
 }
 
 /// [sRGB](https://en.wikipedia.org/wiki/SRGB) space.
@@ -45,6 +210,22 @@ impl From<Rgb8> for RgbSpace {
     }
 }
 
+impl Display for RgbSpace {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let values = self.0.map(|c| 255.0 * c);
+        fmt_components(f, "RGB", ["r", "g", "b"], values, 0)
+    }
+}
+
+impl FromStr for RgbSpace {
+    type Err = ParseColorError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let values = parse_components(s, "RGB", ["r", "g", "b"])?;
+        Ok(Self(values.map(|c| c / 255.0)))
+    }
+}
+
 impl Coordinates for RgbSpace {
     type Value = f64;
 
@@ -68,19 +249,249 @@ impl Proximity for RgbSpace {
 impl Metric for RgbSpace {}
 
 impl ColorSpace for RgbSpace {
-    fn average<I: IntoIterator<Item = Self>>(colors: I) -> Self {
-        let mut sum = [0.0, 0.0, 0.0];
-        let mut len: usize = 0;
-        for color in colors.into_iter() {
-            for i in 0..3 {
-                sum[i] += color[i];
-            }
-            len += 1;
+    fn name() -> &'static str {
+        "sRGB"
+    }
+
+    fn weighted_average<I: IntoIterator<Item = (Self, f64)>>(colors: I) -> Self {
+        Self(weighted_mean(colors.into_iter().map(|(c, w)| (c.0, w))))
+    }
+
+    fn to_rgb8(self) -> Rgb8 {
+        Rgb8::from(self.0.map(|c| (255.0 * c.clamp(0.0, 1.0)).round() as u8))
+    }
+}
+
+/// Compute the weighted mean of some colors, treating their components as rectangular.
+fn weighted_mean<I: IntoIterator<Item = ([f64; 3], f64)>>(colors: I) -> [f64; 3] {
+    let mut sum = [0.0, 0.0, 0.0];
+    let mut total = 0.0;
+    for (color, weight) in colors {
+        for i in 0..3 {
+            sum[i] += weight * color[i];
+        }
+        total += weight;
+    }
+    sum.map(|s| s / total)
+}
+
+/// Position a `[hue, saturation, x]` color in a cylinder, so that hues wrap around.
+fn hue_cylinder([h, s, x]: [f64; 3]) -> [f64; 3] {
+    [s * h.cos(), s * h.sin(), x]
+}
+
+/// Average some weighted `[hue, saturation, x]` colors, using the circular mean of their hues.
+fn hue_average<I: IntoIterator<Item = ([f64; 3], f64)>>(colors: I) -> [f64; 3] {
+    let mut sum = [0.0, 0.0, 0.0, 0.0];
+    let mut total = 0.0;
+    for ([h, s, x], w) in colors {
+        sum[0] += w * h.cos();
+        sum[1] += w * h.sin();
+        sum[2] += w * s;
+        sum[3] += w * x;
+        total += w;
+    }
+
+    let h = sum[1].atan2(sum[0]).rem_euclid(TAU);
+    [h, sum[2] / total, sum[3] / total]
+}
+
+/// The hue of an sRGB color, in radians, given its maximum component and chroma.
+fn rgb_hue([r, g, b]: [f64; 3], max: f64, chroma: f64) -> f64 {
+    let h = if max == r {
+        (g - b) / chroma
+    } else if max == g {
+        (b - r) / chroma + 2.0
+    } else {
+        (r - g) / chroma + 4.0
+    };
+    (h * TAU / 6.0).rem_euclid(TAU)
+}
+
+/// [HSL](https://en.wikipedia.org/wiki/HSL_and_HSV) space.
+///
+/// Colors are stored as `[hue, saturation, lightness]`, with the hue in radians.  Distances are
+/// measured in the HSL cylinder, so hues wrap around.
+#[derive(Clone, Copy, Debug)]
+pub struct HslSpace([f64; 3]);
+
+impl Index<usize> for HslSpace {
+    type Output = f64;
+
+    fn index(&self, i: usize) -> &f64 {
+        &self.0[i]
+    }
+}
+
+impl From<Rgb8> for HslSpace {
+    fn from(rgb8: Rgb8) -> Self {
+        let rgb = RgbSpace::from(rgb8).0;
+        let [r, g, b] = rgb;
+
+        let max = r.max(g).max(b);
+        let min = r.min(g).min(b);
+        let chroma = max - min;
+        let l = (max + min) / 2.0;
+
+        if chroma == 0.0 {
+            return Self([0.0, 0.0, l]);
         }
-        for s in &mut sum {
-            *s /= len as f64;
+
+        let h = rgb_hue(rgb, max, chroma);
+        let s = chroma / (1.0 - (2.0 * l - 1.0).abs());
+
+        Self([h, s, l])
+    }
+}
+
+impl Display for HslSpace {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let [h, s, l] = self.0;
+        let values = [h.to_degrees(), 100.0 * s, 100.0 * l];
+        fmt_components(f, "HSL", ["h", "s", "l"], values, 1)
+    }
+}
+
+impl FromStr for HslSpace {
+    type Err = ParseColorError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let [h, s, l] = parse_components(s, "HSL", ["h", "s", "l"])?;
+        Ok(Self([h.to_radians().rem_euclid(TAU), s / 100.0, l / 100.0]))
+    }
+}
+
+impl Coordinates for HslSpace {
+    type Value = f64;
+
+    fn dims(&self) -> usize {
+        3
+    }
+
+    fn coord(&self, i: usize) -> f64 {
+        hue_cylinder(self.0)[i]
+    }
+}
+
+impl Proximity for HslSpace {
+    type Distance = EuclideanDistance<f64>;
+
+    fn distance(&self, other: &Self) -> Self::Distance {
+        euclidean_distance(&hue_cylinder(self.0), &hue_cylinder(other.0))
+    }
+}
+
+impl Metric for HslSpace {}
+
+impl ColorSpace for HslSpace {
+    fn name() -> &'static str {
+        "HSL"
+    }
+
+    fn weighted_average<I: IntoIterator<Item = (Self, f64)>>(colors: I) -> Self {
+        Self(hue_average(colors.into_iter().map(|(c, w)| (c.0, w))))
+    }
+
+    fn to_rgb8(self) -> Rgb8 {
+        let [h, s, l] = self.0;
+        let a = s * l.min(1.0 - l);
+        let f = |n: f64| {
+            let k = (n + 6.0 * h / PI) % 12.0;
+            let c = l - a * (k - 3.0).min(9.0 - k).clamp(-1.0, 1.0);
+            (255.0 * c.clamp(0.0, 1.0)).round() as u8
+        };
+        Rgb8::from([f(0.0), f(8.0), f(4.0)])
+    }
+}
+
+/// [HSV](https://en.wikipedia.org/wiki/HSL_and_HSV) space.
+///
+/// Colors are stored as `[hue, saturation, value]`, with the hue in radians.  Distances are
+/// measured in the HSV cylinder, so hues wrap around.
+#[derive(Clone, Copy, Debug)]
+pub struct HsvSpace([f64; 3]);
+
+impl Index<usize> for HsvSpace {
+    type Output = f64;
+
+    fn index(&self, i: usize) -> &f64 {
+        &self.0[i]
+    }
+}
+
+impl From<Rgb8> for HsvSpace {
+    fn from(rgb8: Rgb8) -> Self {
+        let rgb = RgbSpace::from(rgb8).0;
+        let [r, g, b] = rgb;
+
+        let max = r.max(g).max(b);
+        let min = r.min(g).min(b);
+        let chroma = max - min;
+
+        if chroma == 0.0 {
+            return Self([0.0, 0.0, max]);
         }
-        Self(sum)
+
+        Self([rgb_hue(rgb, max, chroma), chroma / max, max])
+    }
+}
+
+impl Display for HsvSpace {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let [h, s, v] = self.0;
+        let values = [h.to_degrees(), 100.0 * s, 100.0 * v];
+        fmt_components(f, "HSV", ["h", "s", "v"], values, 1)
+    }
+}
+
+impl FromStr for HsvSpace {
+    type Err = ParseColorError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let [h, s, v] = parse_components(s, "HSV", ["h", "s", "v"])?;
+        Ok(Self([h.to_radians().rem_euclid(TAU), s / 100.0, v / 100.0]))
+    }
+}
+
+impl Coordinates for HsvSpace {
+    type Value = f64;
+
+    fn dims(&self) -> usize {
+        3
+    }
+
+    fn coord(&self, i: usize) -> f64 {
+        hue_cylinder(self.0)[i]
+    }
+}
+
+impl Proximity for HsvSpace {
+    type Distance = EuclideanDistance<f64>;
+
+    fn distance(&self, other: &Self) -> Self::Distance {
+        euclidean_distance(&hue_cylinder(self.0), &hue_cylinder(other.0))
+    }
+}
+
+impl Metric for HsvSpace {}
+
+impl ColorSpace for HsvSpace {
+    fn name() -> &'static str {
+        "HSV"
+    }
+
+    fn weighted_average<I: IntoIterator<Item = (Self, f64)>>(colors: I) -> Self {
+        Self(hue_average(colors.into_iter().map(|(c, w)| (c.0, w))))
+    }
+
+    fn to_rgb8(self) -> Rgb8 {
+        let [h, s, v] = self.0;
+        let f = |n: f64| {
+            let k = (n + 6.0 * h / TAU) % 6.0;
+            let c = v - v * s * k.min(4.0 - k).clamp(0.0, 1.0);
+            (255.0 * c.clamp(0.0, 1.0)).round() as u8
+        };
+        Rgb8::from([f(5.0), f(3.0), f(1.0)])
     }
 }
 
@@ -98,11 +509,12 @@ impl Index<usize> for XyzSpace {
 
 /// The inverse of the sRGB gamma function.
 fn srgb_inv_gamma(t: f64) -> f64 {
-    if t <= 0.040449936 {
-        t / 12.92
-    } else {
-        ((t + 0.055) / 1.055).powf(2.4)
-    }
+    convert::srgb_to_linear(t)
+}
+
+/// The sRGB gamma function.
+fn srgb_gamma(t: f64) -> f64 {
+    convert::linear_to_srgb(t)
 }
 
 impl From<Rgb8> for XyzSpace {
@@ -113,26 +525,25 @@ impl From<Rgb8> for XyzSpace {
         let g = srgb_inv_gamma(rgb[1]);
         let b = srgb_inv_gamma(rgb[2]);
 
-        Self([
-            0.4123808838268995 * r + 0.3575728355732478 * g + 0.1804522977447919 * b,
-            0.2126198631048975 * r + 0.7151387878413206 * g + 0.0721499433963131 * b,
-            0.0193434956789248 * r + 0.1192121694056356 * g + 0.9505065664127130 * b,
-        ])
+        Self(convert::linear_rgb_to_xyz(r, g, b))
     }
 }
 
-/// CIE D50 [white point](https://en.wikipedia.org/wiki/Standard_illuminant).
-const WHITE: XyzSpace = XyzSpace([0.9504060171449392, 0.9999085943425312, 1.089062231497274]);
-
-/// CIE L\*a\*b\* (and L\*u\*v\*) gamma
-fn lab_gamma(t: f64) -> f64 {
-    if t > 216.0 / 24389.0 {
-        t.cbrt()
-    } else {
-        841.0 * t / 108.0 + 4.0 / 29.0
+impl XyzSpace {
+    /// Convert back to 8-bit RGB, clamping out-of-gamut colors.
+    fn to_rgb8(self) -> Rgb8 {
+        linear_to_rgb8(convert::xyz_to_linear_rgb(self.0))
     }
 }
 
+/// Convert linear RGB back to 8-bit sRGB, clamping out-of-gamut colors.
+fn linear_to_rgb8(rgb: [f64; 3]) -> Rgb8 {
+    Rgb8::from(rgb.map(|c| (255.0 * srgb_gamma(c.clamp(0.0, 1.0))).round() as u8))
+}
+
+/// CIE D50 [white point](https://en.wikipedia.org/wiki/Standard_illuminant).
+const WHITE: XyzSpace = XyzSpace(convert::WHITE);
+
 /// [CIE L\*a\*b\*](https://en.wikipedia.org/wiki/CIELAB_color_space) space.
 #[derive(Clone, Copy, Debug)]
 pub struct LabSpace([f64; 3]);
@@ -147,17 +558,105 @@ impl Index<usize> for LabSpace {
 
 impl From<Rgb8> for LabSpace {
     fn from(rgb8: Rgb8) -> Self {
-        let xyz = XyzSpace::from(rgb8);
+        Self(convert::xyz_to_lab(XyzSpace::from(rgb8).0))
+    }
+}
+
+/// Convert rectangular coordinates `[L, x, y]` to cylindrical coordinates `[L, chroma, hue]`, with
+/// the hue in radians.
+fn to_cylindrical(lxy: [f64; 3]) -> [f64; 3] {
+    let [l, x, y] = lxy;
+    [l, x.hypot(y), y.atan2(x).rem_euclid(TAU)]
+}
+
+/// Convert cylindrical coordinates `[L, chroma, hue]` to rectangular coordinates `[L, x, y]`.
+fn from_cylindrical(lch: [f64; 3]) -> [f64; 3] {
+    let [l, c, h] = lch;
+    [l, c * h.cos(), c * h.sin()]
+}
+
+impl LabSpace {
+    /// Convert to cylindrical `[L, chroma, hue]` coordinates, with the hue in radians.
+    pub fn to_lch(self) -> [f64; 3] {
+        to_cylindrical(self.0)
+    }
+
+    /// Compute the [CIEDE2000](https://en.wikipedia.org/wiki/Color_difference#CIEDE2000) color
+    /// difference between two colors.
+    ///
+    /// This is not a metric (it violates the triangle inequality), so it can't be used as the
+    /// distance for nearest neighbor searches.
+    pub fn ciede2000(&self, other: &Self) -> f64 {
+        let [l1, a1, b1] = self.0;
+        let [l2, a2, b2] = other.0;
+
+        let c_bar = (a1.hypot(b1) + a2.hypot(b2)) / 2.0;
+        let c_bar7 = c_bar.powi(7);
+        let g = 0.5 * (1.0 - (c_bar7 / (c_bar7 + 25.0f64.powi(7))).sqrt());
+
+        let a1 = (1.0 + g) * a1;
+        let a2 = (1.0 + g) * a2;
+        let c1 = a1.hypot(b1);
+        let c2 = a2.hypot(b2);
+        let h1 = if c1 == 0.0 { 0.0 } else { b1.atan2(a1).rem_euclid(TAU) };
+        let h2 = if c2 == 0.0 { 0.0 } else { b2.atan2(a2).rem_euclid(TAU) };
+
+        let dl = l2 - l1;
+        let dc = c2 - c1;
+        let dh = if c1 * c2 == 0.0 {
+            0.0
+        } else if (h2 - h1).abs() <= PI {
+            h2 - h1
+        } else if h2 <= h1 {
+            h2 - h1 + TAU
+        } else {
+            h2 - h1 - TAU
+        };
+        let dh = 2.0 * (c1 * c2).sqrt() * (dh / 2.0).sin();
+
+        let l_bar = (l1 + l2) / 2.0;
+        let c_bar = (c1 + c2) / 2.0;
+        let h_bar = if c1 * c2 == 0.0 {
+            h1 + h2
+        } else if (h1 - h2).abs() <= PI {
+            (h1 + h2) / 2.0
+        } else if h1 + h2 < TAU {
+            (h1 + h2 + TAU) / 2.0
+        } else {
+            (h1 + h2 - TAU) / 2.0
+        };
+
+        let t = 1.0 - 0.17 * (h_bar - 30f64.to_radians()).cos()
+            + 0.24 * (2.0 * h_bar).cos()
+            + 0.32 * (3.0 * h_bar + 6f64.to_radians()).cos()
+            - 0.20 * (4.0 * h_bar - 63f64.to_radians()).cos();
+        let d_theta = 30f64.to_radians() * (-((h_bar.to_degrees() - 275.0) / 25.0).powi(2)).exp();
+        let c_bar7 = c_bar.powi(7);
+        let r_c = 2.0 * (c_bar7 / (c_bar7 + 25.0f64.powi(7))).sqrt();
+        let l_bar2 = (l_bar - 50.0).powi(2);
+        let s_l = 1.0 + 0.015 * l_bar2 / (20.0 + l_bar2).sqrt();
+        let s_c = 1.0 + 0.045 * c_bar;
+        let s_h = 1.0 + 0.015 * c_bar * t;
+        let r_t = -(2.0 * d_theta).sin() * r_c;
+
+        let dl = dl / s_l;
+        let dc = dc / s_c;
+        let dh = dh / s_h;
+        (dl * dl + dc * dc + dh * dh + r_t * dc * dh).sqrt()
+    }
+}
 
-        let x = lab_gamma(xyz[0] / WHITE[0]);
-        let y = lab_gamma(xyz[1] / WHITE[1]);
-        let z = lab_gamma(xyz[2] / WHITE[2]);
+impl Display for LabSpace {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        fmt_components(f, "Lab", ["L", "a", "b"], self.0, 1)
+    }
+}
 
-        let l = 116.0 * y - 16.0;
-        let a = 500.0 * (x - y);
-        let b = 200.0 * (y - z);
+impl FromStr for LabSpace {
+    type Err = ParseColorError;
 
-        Self([l, a, b])
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        parse_components(s, "Lab", ["L", "a", "b"]).map(Self)
     }
 }
 
@@ -184,27 +683,54 @@ impl Proximity for LabSpace {
 impl Metric for LabSpace {}
 
 impl ColorSpace for LabSpace {
-    fn average<I: IntoIterator<Item = Self>>(colors: I) -> Self {
-        let mut sum = [0.0, 0.0, 0.0];
-        let mut len: usize = 0;
-        for color in colors.into_iter() {
-            for i in 0..3 {
-                sum[i] += color[i];
-            }
-            len += 1;
-        }
-        for s in &mut sum {
-            *s /= len as f64;
-        }
-        Self(sum)
+    fn name() -> &'static str {
+        "CIE L*a*b*"
+    }
+
+    fn weighted_average<I: IntoIterator<Item = (Self, f64)>>(colors: I) -> Self {
+        Self(weighted_mean(colors.into_iter().map(|(c, w)| (c.0, w))))
+    }
+
+    fn to_rgb8(self) -> Rgb8 {
+        XyzSpace(convert::lab_to_xyz(self.0)).to_rgb8()
     }
 }
 
-/// [CIE L\*u\*v\*](https://en.wikipedia.org/wiki/CIELUV) space.
+/// The distance between two `[L, chroma, hue]` colors, `sqrt(ΔL² + ΔC² + ΔH²)`.
+///
+/// ΔH is the chord between the two hues on the chroma circle, which makes this equal to the
+/// Euclidean distance between the Cartesian forms of the colors.
+fn lch_distance([l1, c1, h1]: [f64; 3], [l2, c2, h2]: [f64; 3]) -> EuclideanDistance<f64> {
+    let dl = l1 - l2;
+    let dc = c1 - c2;
+    let dh = 2.0 * (c1 * c2).sqrt() * ((h1 - h2) / 2.0).sin();
+    EuclideanDistance::from_squared(dl * dl + dc * dc + dh * dh)
+}
+
+/// Average some weighted `[L, chroma, hue]` colors, using the circular mean of their hues.
+///
+/// The hues are also weighted by chroma, so achromatic colors don't pull them towards zero.
+fn lch_average<I: IntoIterator<Item = ([f64; 3], f64)>>(colors: I) -> [f64; 3] {
+    let mut sum = [0.0, 0.0, 0.0, 0.0];
+    let mut total = 0.0;
+    for ([l, c, h], w) in colors {
+        sum[0] += w * l;
+        sum[1] += w * c;
+        sum[2] += w * c * h.cos();
+        sum[3] += w * c * h.sin();
+        total += w;
+    }
+
+    let h = sum[3].atan2(sum[2]).rem_euclid(TAU);
+    [sum[0] / total, sum[1] / total, h]
+}
+
+/// [CIE LCh](https://en.wikipedia.org/wiki/CIELAB_color_space#Cylindrical_model) space, the
+/// cylindrical form of L\*a\*b\*.
 #[derive(Clone, Copy, Debug)]
-pub struct LuvSpace([f64; 3]);
+pub struct LchSpace([f64; 3]);
 
-impl Index<usize> for LuvSpace {
+impl Index<usize> for LchSpace {
     type Output = f64;
 
     fn index(&self, i: usize) -> &f64 {
@@ -212,75 +738,83 @@ impl Index<usize> for LuvSpace {
     }
 }
 
-/// Computes the u' and v' values for L\*u\*v\*.
-fn uv_prime(xyz: &XyzSpace) -> (f64, f64) {
-    let denom = xyz[0] + 15.0 * xyz[1] + 3.0 * xyz[2];
-    if denom == 0.0 {
-        (0.0, 0.0)
-    } else {
-        (4.0 * xyz[0] / denom, 9.0 * xyz[1] / denom)
+impl From<LabSpace> for LchSpace {
+    fn from(lab: LabSpace) -> Self {
+        Self(lab.to_lch())
     }
 }
 
-impl From<Rgb8> for LuvSpace {
+impl From<Rgb8> for LchSpace {
     fn from(rgb8: Rgb8) -> Self {
-        let xyz = XyzSpace::from(rgb8);
+        LabSpace::from(rgb8).into()
+    }
+}
 
-        let (uprime, vprime) = uv_prime(&xyz);
-        let (unprime, vnprime) = uv_prime(&WHITE);
+impl LchSpace {
+    /// Convert back to L\*a\*b\*.
+    pub fn to_lab(self) -> LabSpace {
+        LabSpace(from_cylindrical(self.0))
+    }
+}
 
-        let l = 116.0 * lab_gamma(xyz[1] / WHITE[1]) - 16.0;
-        let u = 13.0 * l * (uprime - unprime);
-        let v = 13.0 * l * (vprime - vnprime);
+impl Display for LchSpace {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let [l, c, h] = self.0;
+        fmt_components(f, "LCh", ["L", "C", "h"], [l, c, h.to_degrees()], 1)
+    }
+}
 
-        Self([l, u, v])
+impl FromStr for LchSpace {
+    type Err = ParseColorError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let [l, c, h] = parse_components(s, "LCh", ["L", "C", "h"])?;
+        Ok(Self([l, c, h.to_radians()]))
     }
 }
 
-impl Coordinates for LuvSpace {
+impl Coordinates for LchSpace {
     type Value = f64;
 
     fn dims(&self) -> usize {
-        self.0.dims()
+        3
     }
 
     fn coord(&self, i: usize) -> f64 {
-        self.0.coord(i)
+        from_cylindrical(self.0)[i]
     }
 }
 
-impl Proximity for LuvSpace {
+impl Proximity for LchSpace {
     type Distance = EuclideanDistance<f64>;
 
     fn distance(&self, other: &Self) -> Self::Distance {
-        euclidean_distance(&self.0, &other.0)
+        lch_distance(self.0, other.0)
     }
 }
 
-impl Metric for LuvSpace {}
+impl Metric for LchSpace {}
 
-impl ColorSpace for LuvSpace {
-    fn average<I: IntoIterator<Item = Self>>(colors: I) -> Self {
-        let mut sum = [0.0, 0.0, 0.0];
-        let mut len: usize = 0;
-        for color in colors.into_iter() {
-            for i in 0..3 {
-                sum[i] += color[i];
-            }
-            len += 1;
-        }
-        for s in &mut sum {
-            *s /= len as f64;
-        }
-        Self(sum)
+impl ColorSpace for LchSpace {
+    fn name() -> &'static str {
+        "CIE LCh"
+    }
+
+    fn weighted_average<I: IntoIterator<Item = (Self, f64)>>(colors: I) -> Self {
+        Self(lch_average(colors.into_iter().map(|(c, w)| (c.0, w))))
+    }
+
+    fn to_rgb8(self) -> Rgb8 {
+        self.to_lab().to_rgb8()
     }
 }
 
-/// [Oklab](https://bottosson.github.io/posts/oklab/) space.
+/// [DIN99](https://de.wikipedia.org/wiki/DIN99-Farbraum) space, a more uniform variant of
+/// L\*a\*b\* standardized in DIN 6176.
 #[derive(Clone, Copy, Debug)]
-pub struct OklabSpace([f64; 3]);
+pub struct Din99Space([f64; 3]);
 
-impl Index<usize> for OklabSpace {
+impl Index<usize> for Din99Space {
     type Output = f64;
 
     fn index(&self, i: usize) -> &f64 {
@@ -288,21 +822,216 @@ impl Index<usize> for OklabSpace {
     }
 }
 
-impl From<Rgb8> for OklabSpace {
-    fn from(rgb8: Rgb8) -> Self {
-        let rgb = RgbSpace::from(rgb8);
-
-        let r = srgb_inv_gamma(rgb[0]);
-        let g = srgb_inv_gamma(rgb[1]);
-        let b = srgb_inv_gamma(rgb[2]);
+impl From<LabSpace> for Din99Space {
+    fn from(lab: LabSpace) -> Self {
+        let [l, a, b] = lab.0;
+        let (sin, cos) = 16f64.to_radians().sin_cos();
 
-        let l = 0.4122214708 * r + 0.5363325363 * g + 0.0514459929 * b;
-        let m = 0.2119034982 * r + 0.6806995451 * g + 0.1073969566 * b;
-        let s = 0.0883024619 * r + 0.2817188376 * g + 0.6299787005 * b;
+        let l99 = 105.51 * (0.0158 * l).ln_1p();
 
-        let l = l.cbrt();
-        let m = m.cbrt();
-        let s = s.cbrt();
+        // Rotate and stretch the a*b* plane, then compress the chroma
+        let e = a * cos + b * sin;
+        let f = 0.7 * (b * cos - a * sin);
+        let g = e.hypot(f);
+        let c99 = (0.045 * g).ln_1p() / 0.045;
+        let h99 = f.atan2(e);
+
+        Self(from_cylindrical([l99, c99, h99]))
+    }
+}
+
+impl From<Rgb8> for Din99Space {
+    fn from(rgb8: Rgb8) -> Self {
+        LabSpace::from(rgb8).into()
+    }
+}
+
+impl Display for Din99Space {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        fmt_components(f, "DIN99", ["L", "a", "b"], self.0, 1)
+    }
+}
+
+impl FromStr for Din99Space {
+    type Err = ParseColorError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        parse_components(s, "DIN99", ["L", "a", "b"]).map(Self)
+    }
+}
+
+impl Coordinates for Din99Space {
+    type Value = f64;
+
+    fn dims(&self) -> usize {
+        self.0.dims()
+    }
+
+    fn coord(&self, i: usize) -> f64 {
+        self.0.coord(i)
+    }
+}
+
+impl Proximity for Din99Space {
+    type Distance = EuclideanDistance<f64>;
+
+    fn distance(&self, other: &Self) -> Self::Distance {
+        euclidean_distance(&self.0, &other.0)
+    }
+}
+
+impl Metric for Din99Space {}
+
+impl ColorSpace for Din99Space {
+    fn name() -> &'static str {
+        "DIN99"
+    }
+
+    fn weighted_average<I: IntoIterator<Item = (Self, f64)>>(colors: I) -> Self {
+        Self(weighted_mean(colors.into_iter().map(|(c, w)| (c.0, w))))
+    }
+}
+
+/// [CIE L\*u\*v\*](https://en.wikipedia.org/wiki/CIELUV) space.
+#[derive(Clone, Copy, Debug)]
+pub struct LuvSpace([f64; 3]);
+
+impl Index<usize> for LuvSpace {
+    type Output = f64;
+
+    fn index(&self, i: usize) -> &f64 {
+        &self.0[i]
+    }
+}
+
+/// Computes the u' and v' values for L\*u\*v\*.
+fn uv_prime(xyz: &XyzSpace) -> (f64, f64) {
+    let denom = xyz[0] + 15.0 * xyz[1] + 3.0 * xyz[2];
+    if denom == 0.0 {
+        (0.0, 0.0)
+    } else {
+        (4.0 * xyz[0] / denom, 9.0 * xyz[1] / denom)
+    }
+}
+
+impl From<Rgb8> for LuvSpace {
+    fn from(rgb8: Rgb8) -> Self {
+        let xyz = XyzSpace::from(rgb8);
+
+        let (uprime, vprime) = uv_prime(&xyz);
+        let (unprime, vnprime) = uv_prime(&WHITE);
+
+        let l = 116.0 * lab_gamma(xyz[1] / WHITE[1]) - 16.0;
+        let u = 13.0 * l * (uprime - unprime);
+        let v = 13.0 * l * (vprime - vnprime);
+
+        Self([l, u, v])
+    }
+}
+
+impl LuvSpace {
+    /// Convert to cylindrical `[L, chroma, hue]` coordinates (CIE LCh(uv), or HCL), with the hue in
+    /// radians.
+    pub fn to_hcluv(self) -> [f64; 3] {
+        to_cylindrical(self.0)
+    }
+
+    /// Convert from cylindrical `[L, chroma, hue]` coordinates, with the hue in radians.
+    pub fn from_hcluv(lch: [f64; 3]) -> Self {
+        Self(from_cylindrical(lch))
+    }
+}
+
+impl Display for LuvSpace {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        fmt_components(f, "Luv", ["L", "u", "v"], self.0, 1)
+    }
+}
+
+impl FromStr for LuvSpace {
+    type Err = ParseColorError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        parse_components(s, "Luv", ["L", "u", "v"]).map(Self)
+    }
+}
+
+impl Coordinates for LuvSpace {
+    type Value = f64;
+
+    fn dims(&self) -> usize {
+        self.0.dims()
+    }
+
+    fn coord(&self, i: usize) -> f64 {
+        self.0.coord(i)
+    }
+}
+
+impl Proximity for LuvSpace {
+    type Distance = EuclideanDistance<f64>;
+
+    fn distance(&self, other: &Self) -> Self::Distance {
+        euclidean_distance(&self.0, &other.0)
+    }
+}
+
+impl Metric for LuvSpace {}
+
+impl ColorSpace for LuvSpace {
+    fn name() -> &'static str {
+        "CIE L*u*v*"
+    }
+
+    fn weighted_average<I: IntoIterator<Item = (Self, f64)>>(colors: I) -> Self {
+        Self(weighted_mean(colors.into_iter().map(|(c, w)| (c.0, w))))
+    }
+
+    fn to_rgb8(self) -> Rgb8 {
+        let [l, u, v] = self.0;
+        if l <= 0.0 {
+            return Rgb8::from([0, 0, 0]);
+        }
+
+        let (unprime, vnprime) = uv_prime(&WHITE);
+        let uprime = u / (13.0 * l) + unprime;
+        let vprime = v / (13.0 * l) + vnprime;
+
+        let y = WHITE[1] * lab_inv_gamma((l + 16.0) / 116.0);
+        let x = y * 9.0 * uprime / (4.0 * vprime);
+        let z = y * (12.0 - 3.0 * uprime - 20.0 * vprime) / (4.0 * vprime);
+
+        XyzSpace([x, y, z]).to_rgb8()
+    }
+}
+
+/// [Oklab](https://bottosson.github.io/posts/oklab/) space.
+#[derive(Clone, Copy, Debug)]
+pub struct OklabSpace([f64; 3]);
+
+impl Index<usize> for OklabSpace {
+    type Output = f64;
+
+    fn index(&self, i: usize) -> &f64 {
+        &self.0[i]
+    }
+}
+
+impl From<Rgb8> for OklabSpace {
+    fn from(rgb8: Rgb8) -> Self {
+        let rgb = RgbSpace::from(rgb8);
+
+        let r = srgb_inv_gamma(rgb[0]);
+        let g = srgb_inv_gamma(rgb[1]);
+        let b = srgb_inv_gamma(rgb[2]);
+
+        let l = 0.4122214708 * r + 0.5363325363 * g + 0.0514459929 * b;
+        let m = 0.2119034982 * r + 0.6806995451 * g + 0.1073969566 * b;
+        let s = 0.0883024619 * r + 0.2817188376 * g + 0.6299787005 * b;
+
+        let l = l.cbrt();
+        let m = m.cbrt();
+        let s = s.cbrt();
 
         Self([
             0.2104542553 * l + 0.7936177850 * m - 0.0040720468 * s,
@@ -312,6 +1041,20 @@ impl From<Rgb8> for OklabSpace {
     }
 }
 
+impl Display for OklabSpace {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        fmt_components(f, "Oklab", ["L", "a", "b"], self.0, 3)
+    }
+}
+
+impl FromStr for OklabSpace {
+    type Err = ParseColorError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        parse_components(s, "Oklab", ["L", "a", "b"]).map(Self)
+    }
+}
+
 impl Coordinates for OklabSpace {
     type Value = f64;
 
@@ -335,18 +1078,836 @@ impl Proximity for OklabSpace {
 impl Metric for OklabSpace {}
 
 impl ColorSpace for OklabSpace {
-    fn average<I: IntoIterator<Item = Self>>(colors: I) -> Self {
-        let mut sum = [0.0, 0.0, 0.0];
-        let mut len: usize = 0;
-        for color in colors.into_iter() {
+    fn name() -> &'static str {
+        "Oklab"
+    }
+
+    fn weighted_average<I: IntoIterator<Item = (Self, f64)>>(colors: I) -> Self {
+        Self(weighted_mean(colors.into_iter().map(|(c, w)| (c.0, w))))
+    }
+
+    fn to_rgb8(self) -> Rgb8 {
+        let [l, a, b] = self.0;
+
+        let l_ = l + 0.3963377774 * a + 0.2158037573 * b;
+        let m_ = l - 0.1055613458 * a - 0.0638541728 * b;
+        let s_ = l - 0.0894841775 * a - 1.2914855480 * b;
+
+        let l = l_ * l_ * l_;
+        let m = m_ * m_ * m_;
+        let s = s_ * s_ * s_;
+
+        linear_to_rgb8([
+            4.0767416621 * l - 3.3077115913 * m + 0.2309699292 * s,
+            -1.2684380046 * l + 2.6097574011 * m - 0.3413193965 * s,
+            -0.0041960863 * l - 0.7034186147 * m + 1.7076147010 * s,
+        ])
+    }
+}
+
+/// [Oklch](https://bottosson.github.io/posts/oklab/#the-oklab-color-space) space, the cylindrical
+/// form of Oklab.
+#[derive(Clone, Copy, Debug)]
+pub struct OklchSpace([f64; 3]);
+
+impl Index<usize> for OklchSpace {
+    type Output = f64;
+
+    fn index(&self, i: usize) -> &f64 {
+        &self.0[i]
+    }
+}
+
+impl From<OklabSpace> for OklchSpace {
+    fn from(oklab: OklabSpace) -> Self {
+        Self(to_cylindrical(oklab.0))
+    }
+}
+
+impl From<Rgb8> for OklchSpace {
+    fn from(rgb8: Rgb8) -> Self {
+        OklabSpace::from(rgb8).into()
+    }
+}
+
+impl OklchSpace {
+    /// Convert back to Oklab.
+    pub fn to_oklab(self) -> OklabSpace {
+        OklabSpace(from_cylindrical(self.0))
+    }
+}
+
+impl Display for OklchSpace {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let [l, c, h] = self.0;
+        fmt_components(f, "OKLCh", ["L", "C", "h"], [l, c, h.to_degrees()], 3)
+    }
+}
+
+impl FromStr for OklchSpace {
+    type Err = ParseColorError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let [l, c, h] = parse_components(s, "OKLCh", ["L", "C", "h"])?;
+        Ok(Self([l, c, h.to_radians()]))
+    }
+}
+
+impl Coordinates for OklchSpace {
+    type Value = f64;
+
+    fn dims(&self) -> usize {
+        3
+    }
+
+    fn coord(&self, i: usize) -> f64 {
+        from_cylindrical(self.0)[i]
+    }
+}
+
+impl Proximity for OklchSpace {
+    type Distance = EuclideanDistance<f64>;
+
+    fn distance(&self, other: &Self) -> Self::Distance {
+        lch_distance(self.0, other.0)
+    }
+}
+
+impl Metric for OklchSpace {}
+
+impl ColorSpace for OklchSpace {
+    fn name() -> &'static str {
+        "Oklch"
+    }
+
+    fn weighted_average<I: IntoIterator<Item = (Self, f64)>>(colors: I) -> Self {
+        Self(lch_average(colors.into_iter().map(|(c, w)| (c.0, w))))
+    }
+
+    fn to_rgb8(self) -> Rgb8 {
+        self.to_oklab().to_rgb8()
+    }
+}
+
+/// The default absolute luminance of sRGB white, in cd/m², for [JzazbzSpace] and [IctcpSpace].
+pub const DEFAULT_PEAK_LUMINANCE: f64 = 203.0;
+
+/// The absolute luminance of sRGB white, if it has been set.
+static PEAK_LUMINANCE: OnceLock<f64> = OnceLock::new();
+
+/// Set the absolute luminance of sRGB white, in cd/m², for [JzazbzSpace] and [IctcpSpace].
+///
+/// The luminance can only be set once.  If it was already set to a different value, that value is
+/// returned as an error.
+pub fn set_peak_luminance(nits: f64) -> Result<(), f64> {
+    match PEAK_LUMINANCE.set(nits) {
+        Ok(()) => Ok(()),
+        Err(_) if peak_luminance() == nits => Ok(()),
+        Err(_) => Err(peak_luminance()),
+    }
+}
+
+/// The absolute luminance of sRGB white, in cd/m².
+pub fn peak_luminance() -> f64 {
+    PEAK_LUMINANCE.get().copied().unwrap_or(DEFAULT_PEAK_LUMINANCE)
+}
+
+/// The SMPTE ST 2084 perceptual quantizer, for luminances in cd/m².
+fn pq(t: f64) -> f64 {
+    const C1: f64 = 3424.0 / 4096.0;
+    const C2: f64 = 2413.0 / 128.0;
+    const C3: f64 = 2392.0 / 128.0;
+    const N: f64 = 2610.0 / 16384.0;
+    const P: f64 = 1.7 * 2523.0 / 32.0;
+
+    let t = (t.max(0.0) / 10000.0).powf(N);
+    ((C1 + C2 * t) / (1.0 + C3 * t)).powf(P)
+}
+
+/// [J<sub>z</sub>a<sub>z</sub>b<sub>z</sub>](https://doi.org/10.1364/OE.25.015131) space.
+#[derive(Clone, Copy, Debug)]
+pub struct JzazbzSpace([f64; 3]);
+
+impl Index<usize> for JzazbzSpace {
+    type Output = f64;
+
+    fn index(&self, i: usize) -> &f64 {
+        &self.0[i]
+    }
+}
+
+impl JzazbzSpace {
+    /// Convert from absolute XYZ coordinates, in cd/m².
+    fn from_xyz([x, y, z]: [f64; 3]) -> Self {
+        const B: f64 = 1.15;
+        const G: f64 = 0.66;
+        const D: f64 = -0.56;
+        const D0: f64 = 1.6295499532821566e-11;
+
+        let (x, y) = (B * x - (B - 1.0) * z, G * y - (G - 1.0) * x);
+
+        let l = pq(0.41478972 * x + 0.579999 * y + 0.0146480 * z);
+        let m = pq(-0.2015100 * x + 1.120649 * y + 0.0531008 * z);
+        let s = pq(-0.0166008 * x + 0.264800 * y + 0.6684799 * z);
+
+        let iz = 0.5 * l + 0.5 * m;
+        let az = 3.524000 * l - 4.066708 * m + 0.542708 * s;
+        let bz = 0.199076 * l + 1.096799 * m - 1.295875 * s;
+        let jz = (1.0 + D) * iz / (1.0 + D * iz) - D0;
+
+        Self([jz, az, bz])
+    }
+}
+
+impl From<Rgb8> for JzazbzSpace {
+    fn from(rgb8: Rgb8) -> Self {
+        let xyz = XyzSpace::from(rgb8);
+        let peak = peak_luminance();
+        Self::from_xyz(xyz.0.map(|c| peak * c))
+    }
+}
+
+impl Display for JzazbzSpace {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        fmt_components(f, "JzAzBz", ["Jz", "az", "bz"], self.0, 4)
+    }
+}
+
+impl FromStr for JzazbzSpace {
+    type Err = ParseColorError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        parse_components(s, "JzAzBz", ["Jz", "az", "bz"]).map(Self)
+    }
+}
+
+impl Coordinates for JzazbzSpace {
+    type Value = f64;
+
+    fn dims(&self) -> usize {
+        self.0.dims()
+    }
+
+    fn coord(&self, i: usize) -> f64 {
+        self.0.coord(i)
+    }
+}
+
+impl Proximity for JzazbzSpace {
+    type Distance = EuclideanDistance<f64>;
+
+    fn distance(&self, other: &Self) -> Self::Distance {
+        euclidean_distance(&self.0, &other.0)
+    }
+}
+
+impl Metric for JzazbzSpace {}
+
+impl ColorSpace for JzazbzSpace {
+    fn name() -> &'static str {
+        "JzAzBz"
+    }
+
+    fn weighted_average<I: IntoIterator<Item = (Self, f64)>>(colors: I) -> Self {
+        Self(weighted_mean(colors.into_iter().map(|(c, w)| (c.0, w))))
+    }
+}
+
+/// [IC<sub>T</sub>C<sub>P</sub>](https://en.wikipedia.org/wiki/ICtCp) space.
+#[derive(Clone, Copy, Debug)]
+pub struct IctcpSpace([f64; 3]);
+
+impl Index<usize> for IctcpSpace {
+    type Output = f64;
+
+    fn index(&self, i: usize) -> &f64 {
+        &self.0[i]
+    }
+}
+
+impl From<Rgb8> for IctcpSpace {
+    fn from(rgb8: Rgb8) -> Self {
+        let rgb = RgbSpace::from(rgb8);
+
+        let r = srgb_inv_gamma(rgb[0]);
+        let g = srgb_inv_gamma(rgb[1]);
+        let b = srgb_inv_gamma(rgb[2]);
+
+        // Convert to BT.2020 primaries, in cd/m²
+        let peak = peak_luminance();
+        let r2020 = peak * (0.627404 * r + 0.329283 * g + 0.043313 * b);
+        let g2020 = peak * (0.069097 * r + 0.919540 * g + 0.011362 * b);
+        let b2020 = peak * (0.016391 * r + 0.088013 * g + 0.895595 * b);
+
+        let l = pq((1688.0 * r2020 + 2146.0 * g2020 + 262.0 * b2020) / 4096.0);
+        let m = pq((683.0 * r2020 + 2951.0 * g2020 + 462.0 * b2020) / 4096.0);
+        let s = pq((99.0 * r2020 + 309.0 * g2020 + 3688.0 * b2020) / 4096.0);
+
+        Self([
+            (2048.0 * l + 2048.0 * m) / 4096.0,
+            (6610.0 * l - 13613.0 * m + 7003.0 * s) / 4096.0,
+            (17933.0 * l - 17390.0 * m - 543.0 * s) / 4096.0,
+        ])
+    }
+}
+
+impl Display for IctcpSpace {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        fmt_components(f, "ICtCp", ["I", "Ct", "Cp"], self.0, 4)
+    }
+}
+
+impl FromStr for IctcpSpace {
+    type Err = ParseColorError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        parse_components(s, "ICtCp", ["I", "Ct", "Cp"]).map(Self)
+    }
+}
+
+impl Coordinates for IctcpSpace {
+    type Value = f64;
+
+    fn dims(&self) -> usize {
+        self.0.dims()
+    }
+
+    fn coord(&self, i: usize) -> f64 {
+        self.0.coord(i)
+    }
+}
+
+impl Proximity for IctcpSpace {
+    type Distance = EuclideanDistance<f64>;
+
+    fn distance(&self, other: &Self) -> Self::Distance {
+        euclidean_distance(&self.0, &other.0)
+    }
+}
+
+impl Metric for IctcpSpace {}
+
+impl ColorSpace for IctcpSpace {
+    fn name() -> &'static str {
+        "ICtCp"
+    }
+
+    fn weighted_average<I: IntoIterator<Item = (Self, f64)>>(colors: I) -> Self {
+        Self(weighted_mean(colors.into_iter().map(|(c, w)| (c.0, w))))
+    }
+}
+
+/// Per-coordinate weights for a [WeightedSpace].
+pub trait ChannelWeights: Copy {
+    /// The weight of each coordinate, e.g. `[L, a, b]` for L\*a\*b\*.
+    fn weights() -> [f64; 3];
+}
+
+/// A color space `C` with a weighted Euclidean distance, `sqrt(Σ wᵢ (xᵢ - yᵢ)²)`.
+///
+/// This is useful to emphasize some channels over others, e.g. lightness in L\*a\*b\*.  The
+/// weights apply to the [Coordinates] of `C`, which are stored pre-scaled by `sqrt(wᵢ)` so that
+/// nearest neighbor searches work unchanged.  Like the other color spaces, comparisons are done
+/// on squared distances, without any square roots.
+#[derive(Clone, Copy, Debug)]
+pub struct WeightedSpace<C, W> {
+    color: C,
+    coords: [f64; 3],
+    weights: PhantomData<W>,
+}
+
+impl<C, W> WeightedSpace<C, W>
+where
+    C: ColorSpace,
+    C::Value: PartialOrd<C::Distance>,
+    W: ChannelWeights,
+{
+    /// Weight the coordinates of a color.
+    pub fn new(color: C) -> Self {
+        let weights = W::weights();
+        let mut coords = [0.0; 3];
+        for (i, coord) in coords.iter_mut().enumerate() {
+            *coord = weights[i].sqrt() * color.coord(i);
+        }
+
+        Self {
+            color,
+            coords,
+            weights: PhantomData,
+        }
+    }
+}
+
+impl<C, W> From<Rgb8> for WeightedSpace<C, W>
+where
+    C: ColorSpace,
+    C::Value: PartialOrd<C::Distance>,
+    W: ChannelWeights,
+{
+    fn from(rgb8: Rgb8) -> Self {
+        Self::new(C::from(rgb8))
+    }
+}
+
+impl<C: Display, W> Display for WeightedSpace<C, W> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        self.color.fmt(f)
+    }
+}
+
+impl<C, W> Coordinates for WeightedSpace<C, W> {
+    type Value = f64;
+
+    fn dims(&self) -> usize {
+        self.coords.dims()
+    }
+
+    fn coord(&self, i: usize) -> f64 {
+        self.coords.coord(i)
+    }
+}
+
+impl<C, W> Proximity for WeightedSpace<C, W> {
+    type Distance = EuclideanDistance<f64>;
+
+    fn distance(&self, other: &Self) -> Self::Distance {
+        euclidean_distance(&self.coords, &other.coords)
+    }
+}
+
+impl<C, W> Metric for WeightedSpace<C, W> {}
+
+impl<C, W> ColorSpace for WeightedSpace<C, W>
+where
+    C: ColorSpace,
+    C::Value: PartialOrd<C::Distance>,
+    W: ChannelWeights,
+{
+    fn name() -> &'static str {
+        C::name()
+    }
+
+    fn weighted_average<I: IntoIterator<Item = (Self, f64)>>(colors: I) -> Self {
+        Self::new(C::weighted_average(colors.into_iter().map(|(c, w)| (c.color, w))))
+    }
+
+    fn to_rgb8(self) -> Rgb8 {
+        self.color.to_rgb8()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use rand::prelude::*;
+    use rand_pcg::Pcg64;
+
+    use std::f64::consts::FRAC_PI_4;
+
+    fn assert_close(a: [f64; 3], b: [f64; 3]) {
+        for i in 0..3 {
+            assert!((a[i] - b[i]).abs() < 1e-9, "{:?} != {:?}", a, b);
+        }
+    }
+
+    #[test]
+    fn test_display_rgb8() {
+        assert_eq!(DisplayRgb8(Rgb8::from([255, 128, 0])).to_string(), "rgb(255, 128, 0)");
+        assert_eq!(format!("{}", DisplayRgb8(Rgb8::from([0, 0, 0]))), "rgb(0, 0, 0)");
+    }
+
+    #[test]
+    fn test_rgb8_from_hex() {
+        assert_eq!(rgb8_from_hex("#FF8800"), Ok(Rgb8::from([255, 136, 0])));
+        assert_eq!(rgb8_from_hex("#ff8800"), Ok(Rgb8::from([255, 136, 0])));
+        assert_eq!(rgb8_from_hex("FF8800"), Ok(Rgb8::from([255, 136, 0])));
+        assert_eq!(rgb8_from_hex("#F80"), Ok(Rgb8::from([255, 136, 0])));
+        assert_eq!(rgb8_from_hex("123"), Ok(Rgb8::from([17, 34, 51])));
+        assert_eq!(rgb8_from_hex("000000"), Ok(Rgb8::from([0, 0, 0])));
+
+        for s in ["", "#", "#FF880", "#FF88000", "#GG8800", "#FF 880", "##FF8800", "+FF8800"] {
+            assert_eq!(rgb8_from_hex(s), Err(ParseColorError(s.to_string())));
+        }
+    }
+
+    /// Check that a color space's Display output parses back to the same color.
+    fn assert_round_trip<C>(tolerance: f64)
+    where
+        C: ColorSpace + FromStr<Err = ParseColorError> + Index<usize, Output = f64>,
+        C::Value: PartialOrd<C::Distance>,
+    {
+        for rgb in [[0, 0, 0], [255, 255, 255], [255, 0, 0], [200, 150, 100], [12, 34, 56]] {
+            let color = C::from(Rgb8::from(rgb));
+            let string = C::to_display_string(Rgb8::from(rgb));
+            let parsed: C = string.parse().unwrap();
+            for i in 0..3 {
+                let error = (parsed[i] - color[i]).abs();
+                assert!(error.total_cmp(&tolerance).is_le(), "{} != {:?}", string, rgb);
+            }
+            assert_eq!(parsed.to_string(), string);
+        }
+    }
+
+    #[test]
+    fn test_display() {
+        assert_eq!(
+            RgbSpace::to_display_string(Rgb8::from([200, 150, 100])),
+            "RGB(r=200, g=150, b=100)",
+        );
+        assert_eq!(
+            LabSpace::to_display_string(Rgb8::from([255, 0, 0])),
+            "Lab(L=53.2, a=80.1, b=67.2)",
+        );
+
+        assert_round_trip::<RgbSpace>(0.5 / 255.0);
+        assert_round_trip::<LabSpace>(0.05);
+        assert_round_trip::<LuvSpace>(0.05);
+        assert_round_trip::<OklabSpace>(0.0005);
+        assert_round_trip::<HslSpace>(0.001);
+        assert_round_trip::<HsvSpace>(0.001);
+        assert_round_trip::<LchSpace>(0.05);
+        assert_round_trip::<OklchSpace>(0.0005);
+        assert_round_trip::<Din99Space>(0.05);
+        assert_round_trip::<JzazbzSpace>(0.00005);
+        assert_round_trip::<IctcpSpace>(0.00005);
+
+        assert!("Lab(L=1, a=2)".parse::<LabSpace>().is_err());
+        assert!("Luv(L=1, a=2, b=3)".parse::<LuvSpace>().is_err());
+        assert!("RGB(r=1, g=2, b=3, a=4)".parse::<RgbSpace>().is_err());
+
+        assert_eq!(
+            HslSpace::to_display_string(Rgb8::from([200, 150, 100])),
+            "HSL(h=30.0, s=47.6, l=58.8)",
+        );
+    }
+
+    #[test]
+    fn test_hsl_hue_wraparound() {
+        let red = HslSpace::from(Rgb8::from([255, 0, 0]));
+        let rose = HslSpace::from(Rgb8::from([255, 0, 8]));
+        let orange = HslSpace::from(Rgb8::from([255, 8, 0]));
+        assert!(rose[0] > 6.0);
+        assert!(orange[0] < 0.1);
+
+        let near = rose.distance(&orange).value();
+        let far = red.distance(&HslSpace::from(Rgb8::from([0, 255, 255]))).value();
+        assert!(near < 0.1, "{}", near);
+        assert!(far > 1.9, "{}", far);
+
+        let avg = HslSpace::average([rose, orange]);
+        assert!(avg[0] < 0.01 || avg[0] > TAU - 0.01, "{}", avg);
+        assert_close(hue_cylinder(avg.0), hue_cylinder(red.0));
+    }
+
+    #[test]
+    fn test_lab_to_lch() {
+        let red = LabSpace::from(Rgb8::from([255, 0, 0])).to_lch();
+        assert!(red[2] > 0.0 && red[2] < FRAC_PI_4, "red hue is {}", red[2]);
+
+        let gray = LabSpace::from(Rgb8::from([128, 128, 128])).to_lch();
+        assert!(gray[1] < 1e-3);
+
+        for rgb in [[255, 0, 0], [0, 255, 0], [0, 0, 255], [12, 34, 56]] {
+            let lab = LabSpace::from(Rgb8::from(rgb));
+            let lch = LchSpace::from(lab);
+            assert_close(lch.to_lab().to_lch(), lch.0);
+            assert_close(lch.to_lab().0, lab.0);
+        }
+    }
+
+    #[test]
+    fn test_ciede2000() {
+        // Test data from Sharma, Wu, and Dalal (2005)
+        #[rustfmt::skip]
+        let data = [
+            ([50.0000, 2.6772, -79.7751], [50.0000, 0.0000, -82.7485], 2.0425),
+            ([50.0000, 3.1571, -77.2803], [50.0000, 0.0000, -82.7485], 2.8615),
+            ([50.0000, 2.8361, -74.0200], [50.0000, 0.0000, -82.7485], 3.4412),
+            ([50.0000, -1.3802, -84.2814], [50.0000, 0.0000, -82.7485], 1.0000),
+            ([50.0000, -1.1848, -84.8006], [50.0000, 0.0000, -82.7485], 1.0000),
+            ([50.0000, -0.9009, -85.5211], [50.0000, 0.0000, -82.7485], 1.0000),
+            ([50.0000, 0.0000, 0.0000], [50.0000, -1.0000, 2.0000], 2.3669),
+            ([50.0000, -1.0000, 2.0000], [50.0000, 0.0000, 0.0000], 2.3669),
+            ([50.0000, 2.4900, -0.0010], [50.0000, -2.4900, 0.0009], 7.1792),
+            ([50.0000, 2.4900, -0.0010], [50.0000, -2.4900, 0.0010], 7.1792),
+            ([50.0000, 2.4900, -0.0010], [50.0000, -2.4900, 0.0011], 7.2195),
+            ([50.0000, 2.4900, -0.0010], [50.0000, -2.4900, 0.0012], 7.2195),
+            ([50.0000, -0.0010, 2.4900], [50.0000, 0.0009, -2.4900], 4.8045),
+            ([50.0000, -0.0010, 2.4900], [50.0000, 0.0010, -2.4900], 4.8045),
+            ([50.0000, -0.0010, 2.4900], [50.0000, 0.0011, -2.4900], 4.7461),
+            ([50.0000, 2.5000, 0.0000], [50.0000, 0.0000, -2.5000], 4.3065),
+            ([50.0000, 2.5000, 0.0000], [73.0000, 25.0000, -18.0000], 27.1492),
+            ([50.0000, 2.5000, 0.0000], [61.0000, -5.0000, 29.0000], 22.8977),
+            ([50.0000, 2.5000, 0.0000], [56.0000, -27.0000, -3.0000], 31.9030),
+            ([50.0000, 2.5000, 0.0000], [58.0000, 24.0000, 15.0000], 19.4535),
+            ([50.0000, 2.5000, 0.0000], [50.0000, 3.1736, 0.5854], 1.0000),
+            ([50.0000, 2.5000, 0.0000], [50.0000, 3.2972, 0.0000], 1.0000),
+            ([50.0000, 2.5000, 0.0000], [50.0000, 1.8634, 0.5757], 1.0000),
+            ([50.0000, 2.5000, 0.0000], [50.0000, 3.2592, 0.3350], 1.0000),
+            ([60.2574, -34.0099, 36.2677], [60.4626, -34.1751, 39.4387], 1.2644),
+            ([63.0109, -31.0961, -5.8663], [62.8187, -29.7946, -4.0864], 1.2630),
+            ([61.2901, 3.7196, -5.3901], [61.4292, 2.2480, -4.9620], 1.8731),
+            ([35.0831, -44.1164, 3.7933], [35.0232, -40.0716, 1.5901], 1.8645),
+            ([22.7233, 20.0904, -46.6940], [23.0331, 14.9730, -42.5619], 2.0373),
+            ([36.4612, 47.8580, 18.3852], [36.2715, 50.5065, 21.2231], 1.4146),
+            ([90.8027, -2.0831, 1.4410], [91.1528, -1.6435, 0.0447], 1.4441),
+            ([90.9257, -0.5406, -0.9208], [88.6381, -0.8985, -0.7239], 1.5381),
+            ([6.7747, -0.2908, -2.4247], [5.8714, -0.0985, -2.2286], 0.6377),
+            ([2.0776, 0.0795, -1.1350], [0.9033, -0.0636, -0.5514], 0.9082),
+        ];
+
+        for (lab1, lab2, expected) in data {
+            let delta = LabSpace(lab1).ciede2000(&LabSpace(lab2));
+            assert!((delta - expected).abs() < 1e-4, "{:?} {:?}: {}", lab1, lab2, delta);
+            assert!((LabSpace(lab2).ciede2000(&LabSpace(lab1)) - delta).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn test_lch() {
+        let red = LchSpace::from(Rgb8::from([255, 0, 0]));
+        let cyan = LchSpace::from(Rgb8::from([0, 255, 255]));
+        let lab = LabSpace::from(Rgb8::from([255, 0, 0]))
+            .distance(&LabSpace::from(Rgb8::from([0, 255, 255])))
+            .value();
+        assert!((red.distance(&cyan).value() - lab).abs() < 1e-9);
+
+        // A gray has no hue, so it shouldn't affect the average hue
+        let gray = LchSpace([50.0, 0.0, 0.0]);
+        let avg = LchSpace::average([gray, red]);
+        assert!((avg[1] - red[1] / 2.0).abs() < 1e-9);
+        assert!((avg[2] - red[2]).abs() < 1e-9);
+
+        let avg = LchSpace::average([gray, gray]);
+        assert_close(avg.0, gray.0);
+    }
+
+    #[test]
+    fn test_jzazbz() {
+        // Reference values from the colour-science implementation
+        let jzazbz = JzazbzSpace::from_xyz([0.20654008, 0.12197225, 0.05136952]);
+        for (x, y) in jzazbz.0.iter().zip([0.0053504, 0.0092430, 0.0052600]) {
+            assert!((x - y).abs() < 1e-4, "{:?}", jzazbz);
+        }
+
+        let white = JzazbzSpace::from(Rgb8::from([255, 255, 255]));
+        assert!(white[1].abs() < 1e-3 && white[2].abs() < 1e-3, "{:?}", white);
+    }
+
+    #[test]
+    fn test_din99() {
+        // L99 is scaled so that white stays at 100
+        let white = Din99Space::from(LabSpace([100.0, 0.0, 0.0]));
+        assert!((white[0] - 100.0).abs() < 0.01, "{:?}", white);
+        assert_eq!([white[1], white[2]], [0.0, 0.0]);
+
+        // DIN99 compresses chroma, so saturated colors are closer together than in Lab
+        let lab = |rgb| LabSpace::from(Rgb8::from(rgb));
+        let din99 = |rgb| Din99Space::from(Rgb8::from(rgb));
+        let lab_distance = lab([255, 0, 0]).distance(&lab([0, 0, 255])).value();
+        let din99_distance = din99([255, 0, 0]).distance(&din99([0, 0, 255])).value();
+        assert!(din99_distance < lab_distance / 2.0, "{} {}", din99_distance, lab_distance);
+
+        // The a* axis is rotated by 16°
+        let red = Din99Space::from(LabSpace([50.0, 10.0, 0.0]));
+        let hue = red[2].atan2(red[1]);
+        assert!(hue < 0.0 && hue > -16f64.to_radians(), "{}", hue);
+    }
+
+    #[test]
+    fn test_oklch() {
+        let oklab = OklabSpace::from(Rgb8::from([12, 34, 56]));
+        assert_close(OklchSpace::from(oklab).to_oklab().0, oklab.0);
+
+        let a = OklchSpace([0.5, 0.1, 10f64.to_radians()]);
+        let b = OklchSpace([0.5, 0.1, 350f64.to_radians()]);
+        let avg = OklchSpace::average([a, b]);
+        assert!(avg[2] < 1e-9 || avg[2] > TAU - 1e-9, "{}", avg);
+        assert!((avg[1] - 0.1).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_ictcp() {
+        // White is achromatic, with the PQ-encoded intensity of the peak luminance
+        let white = IctcpSpace::from(Rgb8::from([255, 255, 255]));
+        assert!((white[0] - pq(peak_luminance())).abs() < 1e-4, "{:?}", white);
+        assert!(white[1].abs() < 1e-4 && white[2].abs() < 1e-4, "{:?}", white);
+
+        let black = IctcpSpace::from(Rgb8::from([0, 0, 0]));
+        assert!(black[0] < 1e-6, "{:?}", black);
+    }
+
+    #[test]
+    fn test_luv_to_hcluv() {
+        for rgb in [[255, 0, 0], [0, 255, 0], [0, 0, 255], [12, 34, 56]] {
+            let luv = LuvSpace::from(Rgb8::from(rgb));
+            assert_close(LuvSpace::from_hcluv(luv.to_hcluv()).0, luv.0);
+        }
+    }
+
+    #[test]
+    fn test_hsv() {
+        let red = HsvSpace::from(Rgb8::from([255, 0, 0]));
+        let green = HsvSpace::from(Rgb8::from([0, 255, 0]));
+        let blue = HsvSpace::from(Rgb8::from([0, 0, 255]));
+        assert_close(red.0, [0.0, 1.0, 1.0]);
+        assert_close(green.0, [TAU / 3.0, 1.0, 1.0]);
+        assert_close(blue.0, [2.0 * TAU / 3.0, 1.0, 1.0]);
+
+        for r in (0..=255).step_by(15) {
+            for g in (0..=255).step_by(15) {
+                for b in (0..=255).step_by(15) {
+                    let rgb8 = Rgb8::from([r, g, b]);
+                    assert_eq!(HsvSpace::from(rgb8).to_rgb8(), rgb8);
+                }
+            }
+        }
+    }
+
+    /// Check that converting random colors to `C` and back is accurate to within ±1.
+    fn check_round_trip<C: ColorSpace>()
+    where
+        C::Value: PartialOrd<C::Distance>,
+    {
+        let mut rng = Pcg64::seed_from_u64(0);
+        for _ in 0..10000 {
+            let rgb8 = Rgb8::from(rng.gen::<[u8; 3]>());
+            let back = C::from(rgb8).to_rgb8();
             for i in 0..3 {
-                sum[i] += color[i];
+                assert!(rgb8[i].abs_diff(back[i]) <= 1, "{}: {:?} != {:?}", C::name(), rgb8, back);
             }
-            len += 1;
         }
-        for s in &mut sum {
-            *s /= len as f64;
+    }
+
+    #[test]
+    fn test_to_rgb8() {
+        check_round_trip::<RgbSpace>();
+        check_round_trip::<HslSpace>();
+        check_round_trip::<HsvSpace>();
+        check_round_trip::<LabSpace>();
+        check_round_trip::<LchSpace>();
+        check_round_trip::<LuvSpace>();
+        check_round_trip::<OklabSpace>();
+        check_round_trip::<OklchSpace>();
+
+        // Out-of-gamut colors are clamped
+        assert_eq!(LabSpace([100.0, 0.0, -200.0]).to_rgb8()[2], 255);
+        assert_eq!(RgbSpace([-0.5, 0.5, 1.5]).to_rgb8(), Rgb8::from([0, 128, 255]));
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_to_rgb8_unsupported() {
+        JzazbzSpace::from(Rgb8::from([255, 0, 0])).to_rgb8();
+    }
+
+    #[test]
+    fn test_geometric_median() {
+        let colors = [
+            LabSpace([50.0, 0.0, 0.0]),
+            LabSpace([50.0, 1.0, 0.0]),
+            LabSpace([50.0, 0.0, 1.0]),
+            LabSpace([50.0, 90.0, 0.0]),
+        ];
+
+        let average = LabSpace::average(colors);
+        assert_close(average.0, [50.0, 22.75, 0.25]);
+
+        // The outlier barely moves the median away from the cluster
+        let median = LabSpace::geometric_median(colors);
+        assert!(median.distance(&colors[0]).value() < 2.0, "{:?}", median);
+
+        let weighted = LabSpace::weighted_average([(colors[0], 3.0), (colors[1], 1.0)]);
+        assert_close(weighted.0, [50.0, 0.25, 0.0]);
+    }
+
+    #[test]
+    fn test_lerp() {
+        let black = LabSpace::from(Rgb8::from([0, 0, 0]));
+        let white = LabSpace::from(Rgb8::from([255, 255, 255]));
+
+        let gray = black.lerp(white, 0.5);
+        assert!((gray.0[0] - 50.0).abs() < 1e-3, "{:?}", gray);
+        assert!(gray.0[1].abs() < 1e-3 && gray.0[2].abs() < 1e-3, "{:?}", gray);
+
+        let a = LabSpace([30.0, 20.0, -40.0]);
+        let b = LabSpace([70.0, -10.0, 5.0]);
+        assert_close(a.lerp(b, 0.0).0, a.0);
+        assert_close(a.lerp(b, 1.0).0, b.0);
+        assert_close(a.lerp(b, 0.25).0, [40.0, 12.5, -28.75]);
+    }
+
+    fn check_distance_squared<C: ColorSpace>()
+    where
+        C::Value: PartialOrd<C::Distance>,
+    {
+        let black = C::from(Rgb8::from([0, 0, 0]));
+        // Plain comparisons against f64 would pick up the C::Value: PartialOrd<C::Distance> bound
+        let zero: f64 = black.distance_squared(black);
+        assert!(zero.total_cmp(&0.0).is_eq());
+
+        let mut rng = Pcg64::seed_from_u64(0);
+        for _ in 0..100 {
+            let a = C::from(Rgb8::from(rng.gen::<[u8; 3]>()));
+            let b = C::from(Rgb8::from(rng.gen::<[u8; 3]>()));
+            let distance: f64 = a.distance(&b).value();
+            let squared: f64 = a.distance_squared(b);
+            let tolerance: f64 = 1e-9 * squared.max(1.0);
+            assert!((squared - distance * distance).abs().total_cmp(&tolerance).is_le());
+        }
+    }
+
+    #[test]
+    fn test_distance_squared() {
+        check_distance_squared::<RgbSpace>();
+        check_distance_squared::<HslSpace>();
+        check_distance_squared::<LabSpace>();
+        check_distance_squared::<LchSpace>();
+        check_distance_squared::<OklabSpace>();
+
+        let white = LabSpace::from(Rgb8::from([255, 255, 255]));
+        let black = LabSpace::from(Rgb8::from([0, 0, 0]));
+        assert!((black.distance_squared(white) - 10000.0).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_weighted() {
+        #[derive(Clone, Copy, Debug)]
+        struct Unweighted;
+
+        impl ChannelWeights for Unweighted {
+            fn weights() -> [f64; 3] {
+                [1.0; 3]
+            }
+        }
+
+        #[derive(Clone, Copy, Debug)]
+        struct Lightness;
+
+        impl ChannelWeights for Lightness {
+            fn weights() -> [f64; 3] {
+                [100.0, 1.0, 1.0]
+            }
         }
-        Self(sum)
+
+        let gray = LabSpace([50.0, 0.0, 0.0]);
+        let lighter = LabSpace([52.0, 0.0, 0.0]);
+        let redder = LabSpace([50.0, 10.0, 0.0]);
+
+        let plain = |lab: LabSpace| WeightedSpace::<_, Unweighted>::new(lab);
+        assert!((plain(gray).distance(&plain(lighter)).value() - 2.0).abs() < 1e-9);
+        assert!((plain(gray).distance(&plain(redder)).value() - 10.0).abs() < 1e-9);
+
+        // With the lightness weighted heavily, L differences dominate
+        let weighted = |lab: LabSpace| WeightedSpace::<_, Lightness>::new(lab);
+        assert!((weighted(gray).distance(&weighted(lighter)).value() - 20.0).abs() < 1e-9);
+        assert!((weighted(gray).distance(&weighted(redder)).value() - 10.0).abs() < 1e-9);
+
+        let average = WeightedSpace::average([weighted(gray), weighted(lighter)]);
+        assert_close(average.color.0, [51.0, 0.0, 0.0]);
+        assert_close(average.coords, [510.0, 0.0, 0.0]);
+
+        assert_eq!(weighted(gray).to_string(), gray.to_string());
     }
 }
@@ -0,0 +1,133 @@
+//! Conversions between sRGB, CIE XYZ, and CIE L\*a\*b\*.
+//!
+//! sRGB components are in the range [0, 1], and XYZ is scaled so that white has Y = 1.
+
+/// The reference [white point](https://en.wikipedia.org/wiki/Standard_illuminant) for L\*a\*b\*
+/// and L\*u\*v\*, in XYZ.
+pub const WHITE: [f64; 3] = [0.9504060171449392, 0.9999085943425312, 1.089062231497274];
+
+/// Convert a gamma-encoded sRGB component to linear light.
+pub fn srgb_to_linear(c: f64) -> f64 {
+    if c <= 0.040449936 {
+        c / 12.92
+    } else {
+        ((c + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+/// Convert a linear light component to gamma-encoded sRGB.
+pub fn linear_to_srgb(c: f64) -> f64 {
+    if c <= 0.0031308 {
+        12.92 * c
+    } else {
+        1.055 * c.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+/// Convert linear sRGB to XYZ.
+pub fn linear_rgb_to_xyz(r: f64, g: f64, b: f64) -> [f64; 3] {
+    [
+        0.4123808838268995 * r + 0.3575728355732478 * g + 0.1804522977447919 * b,
+        0.2126198631048975 * r + 0.7151387878413206 * g + 0.0721499433963131 * b,
+        0.0193434956789248 * r + 0.1192121694056356 * g + 0.950506566412713 * b,
+    ]
+}
+
+/// Convert XYZ to linear sRGB.  Out-of-gamut colors are not clamped.
+///
+/// This is the matrix given in IEC 61966-2-1, and [linear_rgb_to_xyz()] uses its exact inverse.
+pub fn xyz_to_linear_rgb([x, y, z]: [f64; 3]) -> [f64; 3] {
+    [
+        3.2410 * x - 1.5374 * y - 0.4986 * z,
+        -0.9692 * x + 1.8760 * y + 0.0416 * z,
+        0.0556 * x - 0.2040 * y + 1.0570 * z,
+    ]
+}
+
+/// CIE L\*a\*b\* (and L\*u\*v\*) gamma.
+pub(super) fn lab_gamma(t: f64) -> f64 {
+    if t > 216.0 / 24389.0 {
+        t.cbrt()
+    } else {
+        841.0 * t / 108.0 + 4.0 / 29.0
+    }
+}
+
+/// The inverse of [lab_gamma].
+pub(super) fn lab_inv_gamma(t: f64) -> f64 {
+    if t > 6.0 / 29.0 {
+        t * t * t
+    } else {
+        108.0 * (t - 4.0 / 29.0) / 841.0
+    }
+}
+
+/// Convert XYZ to `[L, a, b]`.
+pub fn xyz_to_lab(xyz: [f64; 3]) -> [f64; 3] {
+    let x = lab_gamma(xyz[0] / WHITE[0]);
+    let y = lab_gamma(xyz[1] / WHITE[1]);
+    let z = lab_gamma(xyz[2] / WHITE[2]);
+
+    [116.0 * y - 16.0, 500.0 * (x - y), 200.0 * (y - z)]
+}
+
+/// Convert `[L, a, b]` to XYZ.
+pub fn lab_to_xyz([l, a, b]: [f64; 3]) -> [f64; 3] {
+    let y = (l + 16.0) / 116.0;
+    let x = y + a / 500.0;
+    let z = y - b / 200.0;
+
+    [
+        WHITE[0] * lab_inv_gamma(x),
+        WHITE[1] * lab_inv_gamma(y),
+        WHITE[2] * lab_inv_gamma(z),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(a: [f64; 3], b: [f64; 3], epsilon: f64) {
+        for i in 0..3 {
+            assert!((a[i] - b[i]).abs() < epsilon, "{:?} != {:?}", a, b);
+        }
+    }
+
+    #[test]
+    fn test_srgb_linear() {
+        for i in 0..=255 {
+            let c = i as f64 / 255.0;
+            assert!((linear_to_srgb(srgb_to_linear(c)) - c).abs() < 1e-9);
+        }
+        assert_eq!(srgb_to_linear(0.0), 0.0);
+        assert!((srgb_to_linear(1.0) - 1.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_xyz_lab() {
+        assert_close(xyz_to_lab(WHITE), [100.0, 0.0, 0.0], 1e-9);
+        assert_close(xyz_to_lab([0.0; 3]), [0.0, 0.0, 0.0], 1e-9);
+
+        for lab in [[50.0, 20.0, -30.0], [5.0, -1.0, 1.0], [90.0, -80.0, 70.0]] {
+            assert_close(xyz_to_lab(lab_to_xyz(lab)), lab, 1e-9);
+        }
+    }
+
+    #[test]
+    fn test_round_trip() {
+        for r in (0..=255).step_by(15) {
+            for g in (0..=255).step_by(15) {
+                for b in (0..=255).step_by(15) {
+                    let srgb = [r, g, b].map(|c| c as f64 / 255.0);
+                    let [lr, lg, lb] = srgb.map(srgb_to_linear);
+                    let xyz = linear_rgb_to_xyz(lr, lg, lb);
+                    assert_close(lab_to_xyz(xyz_to_lab(xyz)), xyz, 1e-9);
+
+                    let back = xyz_to_linear_rgb(xyz).map(linear_to_srgb);
+                    assert_close(back, srgb, 1e-9);
+                }
+            }
+        }
+    }
+}
@@ -1,14 +1,16 @@
 //! Linear orders for colors.
 
-use super::source::ColorSource;
-use super::Rgb8;
+use super::source::{ColorSource, HasPositions};
+use super::{LabSpace, Rgb8, XyzSpace};
 
-use crate::hilbert::hilbert_point;
+use crate::hilbert::{hilbert_index, hilbert_point, moore_point, path_locality};
+use crate::peano::{peano_digits, peano_index};
 
 use rand::seq::SliceRandom;
 use rand::Rng;
 
-use std::cmp::Ordering;
+use std::cmp::{Ordering, Reverse};
+use std::collections::HashMap;
 
 /// An iterator over all colors from a source.
 #[derive(Debug)]
@@ -25,6 +27,20 @@ impl<S: ColorSource> From<S> for ColorSourceIter<S> {
     }
 }
 
+/// Advance to the next coordinates in a space, clearing them after the last one.
+pub(super) fn next_coords(coords: &mut Vec<usize>, dims: &[usize]) {
+    for i in 0..dims.len() {
+        coords[i] += 1;
+        if coords[i] < dims[i] {
+            break;
+        } else if i == dims.len() - 1 {
+            coords.clear();
+        } else {
+            coords[i] = 0;
+        }
+    }
+}
+
 impl<S: ColorSource> Iterator for ColorSourceIter<S> {
     type Item = Rgb8;
 
@@ -34,19 +50,7 @@ impl<S: ColorSource> Iterator for ColorSourceIter<S> {
         }
 
         let color = self.source.get_color(&self.coords);
-
-        let dims = self.source.dimensions();
-        for i in 0..dims.len() {
-            self.coords[i] += 1;
-            if self.coords[i] < dims[i] {
-                break;
-            } else if i == dims.len() - 1 {
-                self.coords.clear();
-            } else {
-                self.coords[i] = 0;
-            }
-        }
-
+        next_coords(&mut self.coords, self.source.dimensions());
         Some(color)
     }
 }
@@ -106,13 +110,95 @@ impl PartialOrd for Hue {
     }
 }
 
-/// Iterate over colors sorted by their hue.
+/// Wrapper for breaking ties by luminance, from the 8-bit color components.
+#[derive(Debug, Eq, Ord, PartialEq, PartialOrd)]
+struct LuminanceKey(u8);
+
+impl From<Rgb8> for LuminanceKey {
+    fn from(rgb8: Rgb8) -> Self {
+        // Rec. 709 weights, in fixed point
+        let [r, g, b] = rgb8.0.map(u32::from);
+        Self(((2126 * r + 7152 * g + 722 * b) / 10000) as u8)
+    }
+}
+
+/// Wrapper for sorting colors by HSL saturation.
+#[derive(Debug, Eq, PartialEq)]
+struct Saturation {
+    /// The chroma, max(R, G, B) - min(R, G, B).
+    num: i32,
+    /// The chroma of the most saturated color with the same lightness.
+    denom: i32,
+}
+
+impl From<Rgb8> for Saturation {
+    fn from(rgb8: Rgb8) -> Self {
+        let max = rgb8.0.into_iter().max().unwrap() as i32;
+        let min = rgb8.0.into_iter().min().unwrap() as i32;
+
+        let num = max - min;
+        let mut denom = 255 - (max + min - 255).abs();
+        if denom == 0 {
+            // Black or white
+            denom = 1;
+        }
+
+        Self { num, denom }
+    }
+}
+
+impl Ord for Saturation {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (self.num * other.denom).cmp(&(other.num * self.denom))
+    }
+}
+
+impl PartialOrd for Saturation {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Iterate over colors sorted by their hue, breaking ties by luminance.
 pub fn hue_sorted<S: ColorSource>(source: S) -> Vec<Rgb8> {
     let mut colors: Vec<_> = ColorSourceIter::from(source).collect();
-    colors.sort_by_key(|c| Hue::from(*c));
+    colors.sort_unstable_by_key(|c| (Hue::from(*c), LuminanceKey::from(*c)));
+    colors
+}
+
+/// Iterate over colors from most to least saturated, breaking ties by hue, then lightness.
+pub fn saturation_sorted<S: ColorSource>(source: S) -> Vec<Rgb8> {
+    let mut colors: Vec<_> = ColorSourceIter::from(source).collect();
+    colors.sort_by_key(|c| {
+        let max = *c.0.iter().max().unwrap() as u16;
+        let min = *c.0.iter().min().unwrap() as u16;
+        let lightness = max + min;
+        (Reverse(Saturation::from(*c)), Hue::from(*c), lightness)
+    });
     colors
 }
 
+/// Iterate over distinct colors, most frequent first, breaking ties by hue.
+pub fn by_frequency_then_hue<S: ColorSource>(source: S) -> Vec<Rgb8> {
+    let mut counts = HashMap::new();
+    for color in ColorSourceIter::from(source) {
+        *counts.entry(color.0).or_insert(0usize) += 1;
+    }
+
+    let mut colors: Vec<_> = counts.into_iter().collect();
+    colors.sort_by_key(|(c, n)| (Reverse(*n), Hue::from(Rgb8::from(*c)), *c));
+    colors.into_iter().map(|(c, _)| Rgb8::from(c)).collect()
+}
+
+/// Iterate over colors from darkest to brightest, by relative luminance.
+pub fn luminance_sorted<S: ColorSource>(source: S) -> Vec<Rgb8> {
+    let mut colors: Vec<_> = ColorSourceIter::from(source)
+        .map(|c| (c, XyzSpace::from(c)[1]))
+        .collect();
+    colors.sort_by(|a, b| a.1.total_cmp(&b.1));
+    colors.into_iter().map(|(c, _)| c).collect()
+}
+
 /// Iterate over colors in random order.
 pub fn shuffled<S: ColorSource, R: Rng>(source: S, rng: &mut R) -> Vec<Rgb8> {
     let mut colors: Vec<_> = ColorSourceIter::from(source).collect();
@@ -120,14 +206,35 @@ pub fn shuffled<S: ColorSource, R: Rng>(source: S, rng: &mut R) -> Vec<Rgb8> {
     colors
 }
 
+/// Pick `n` colors uniformly at random, or all of them if there aren't that many.
+///
+/// This uses [reservoir sampling](https://en.wikipedia.org/wiki/Reservoir_sampling), so only the
+/// sampled colors are kept in memory.
+pub fn sample_random<S: ColorSource, R: Rng>(source: S, n: usize, rng: &mut R) -> Vec<Rgb8> {
+    let mut sample = Vec::with_capacity(n.min(source.total_count()));
+
+    for (i, color) in ColorSourceIter::from(source).enumerate() {
+        if i < n {
+            sample.push(color);
+        } else {
+            let j = rng.gen_range(0..=i);
+            if j < n {
+                sample[j] = color;
+            }
+        }
+    }
+
+    sample
+}
+
 /// ceil(log_2(n)). for rounding up to powers of 2.
 fn log2(n: usize) -> u32 {
     let nbits = 8 * std::mem::size_of::<usize>() as u32;
     nbits - (n - 1).leading_zeros()
 }
 
-/// Iterate over colors in Morton order (Z-order).
-pub fn morton<S: ColorSource>(source: S) -> Vec<Rgb8> {
+/// Iterate over colors in Z-order, optionally with the bits of the index reversed.
+fn z_order<S: ColorSource>(source: S, reversed: bool) -> Vec<Rgb8> {
     let mut colors = Vec::new();
 
     let dims = source.dimensions();
@@ -137,10 +244,12 @@ pub fn morton<S: ColorSource>(source: S) -> Vec<Rgb8> {
 
     let size = 1usize << nbits;
     let mut coords = vec![0; ndims];
-    for i in 0..size {
-        for x in &mut coords {
-            *x = 0;
+    for mut i in 0..size {
+        if reversed && nbits > 0 {
+            i = i.reverse_bits() >> (usize::BITS as usize - nbits);
         }
+
+        coords.fill(0);
         for j in 0..nbits {
             let bit = (i >> j) & 1;
             coords[j % ndims] |= bit << (j / ndims);
@@ -153,6 +262,60 @@ pub fn morton<S: ColorSource>(source: S) -> Vec<Rgb8> {
     colors
 }
 
+/// Iterate over colors in Morton order (Z-order).
+pub fn morton<S: ColorSource>(source: S) -> Vec<Rgb8> {
+    z_order(source, false)
+}
+
+/// Extract every third bit of `i`, starting from the lowest.
+fn compact_by_3(mut i: u64) -> u64 {
+    i &= 0x1249_2492_4924_9249;
+    i = (i ^ (i >> 2)) & 0x10c3_0c30_c30c_30c3;
+    i = (i ^ (i >> 4)) & 0x100f_00f0_0f00_f00f;
+    i = (i ^ (i >> 8)) & 0x001f_0000_ff00_00ff;
+    i = (i ^ (i >> 16)) & 0x001f_0000_0000_ffff;
+    i = (i ^ (i >> 32)) & 0x001f_ffff;
+    i
+}
+
+/// Iterate over colors in Morton order, specialized for 3D sources.
+///
+/// This produces the same order as [morton()], but de-interleaves each index with a fixed
+/// sequence of bit operations instead of a loop over its bits.  Sources that aren't 3D fall back
+/// to [morton()].
+pub fn morton3d<S: ColorSource>(source: S) -> Vec<Rgb8> {
+    let dims = source.dimensions();
+    if dims.len() != 3 {
+        return morton(source);
+    }
+
+    let size = 1u64 << (3 * log2(*dims.iter().max().unwrap()));
+
+    let mut colors = Vec::new();
+    for i in 0..size {
+        let coords = [
+            compact_by_3(i) as usize,
+            compact_by_3(i >> 1) as usize,
+            compact_by_3(i >> 2) as usize,
+        ];
+        if coords.iter().zip(dims.iter()).all(|(x, n)| x < n) {
+            colors.push(source.get_color(&coords));
+        }
+    }
+
+    colors
+}
+
+/// Iterate over colors in van der Corput order, a quasi-random, low-discrepancy order.
+///
+/// This is bit-reversed Morton order, a multidimensional analogue of the
+/// [van der Corput sequence](https://en.wikipedia.org/wiki/Van_der_Corput_sequence).  Every
+/// prefix of the sequence is spread evenly over the color cube: each aligned run of 2<sup>d</sup>
+/// colors touches every octant once, and so on recursively.
+pub fn van_der_corput<S: ColorSource>(source: S) -> Vec<Rgb8> {
+    z_order(source, true)
+}
+
 /// Iterate over colors in Hilbert curve order.
 pub fn hilbert<S: ColorSource>(source: S) -> Vec<Rgb8> {
     let mut colors = Vec::new();
@@ -176,21 +339,503 @@ pub fn hilbert<S: ColorSource>(source: S) -> Vec<Rgb8> {
     colors
 }
 
-/// Stripe an ordered list of colors, to reduce artifacts in the generated image.
+/// Iterate over colors in 3D Hilbert curve order, treating the source as a cube.
+///
+/// Unlike [hilbert()], which uses a compact index that follows the shape of the source, this
+/// traverses the smallest enclosing power-of-two cube.  Sources that aren't 3D fall back to
+/// [hilbert()].
+pub fn hilbert3d<S: ColorSource>(source: S) -> Vec<Rgb8> {
+    let dims = source.dimensions();
+    if dims.len() != 3 {
+        return hilbert(source);
+    }
+
+    let bits = [log2(*dims.iter().max().unwrap()); 3];
+    let size = 1usize << (3 * bits[0]);
+
+    let mut colors = Vec::new();
+    let mut coords = [0; 3];
+    for i in 0..size {
+        hilbert_point(i, &bits, &mut coords);
+        if coords.iter().zip(dims.iter()).all(|(x, n)| x < n) {
+            colors.push(source.get_color(&coords));
+        }
+    }
+
+    colors
+}
+
+/// Iterate over colors in Moore curve order.
+///
+/// Only square, power-of-two sized 2D sources are supported; anything else falls back to
+/// [hilbert()].
+pub fn moore<S: ColorSource>(source: S) -> Vec<Rgb8> {
+    let dims = source.dimensions();
+    if dims.len() != 2 || dims[0] != dims[1] || !dims[0].is_power_of_two() {
+        return hilbert(source);
+    }
+
+    let bits = log2(dims[0]);
+    let mut coords = [0, 0];
+    (0..dims[0] * dims[1])
+        .map(|i| {
+            moore_point(i, bits, &mut coords);
+            source.get_color(&coords)
+        })
+        .collect()
+}
+
+/// Iterate over colors in Peano curve order.
+pub fn peano<S: ColorSource>(source: S) -> Vec<Rgb8> {
+    let dims = source.dimensions();
+    let digits = peano_digits(*dims.iter().max().unwrap());
+
+    // The enclosing power-of-3 grid can be much larger than the source, so sort by index rather
+    // than iterating over the whole curve
+    let mut keyed = Vec::new();
+    let mut coords = vec![0; dims.len()];
+    while !coords.is_empty() {
+        keyed.push((peano_index(&coords, digits), source.get_color(&coords)));
+        next_coords(&mut coords, dims);
+    }
+
+    keyed.sort_by_key(|(index, _)| *index);
+    keyed.into_iter().map(|(_, color)| color).collect()
+}
+
+/// Iterate over colors along anti-diagonals, i.e. in order of the sum of their coordinates.
+pub fn diagonal<S: ColorSource>(source: S) -> Vec<Rgb8> {
+    let dims = source.dimensions();
+
+    let mut keyed = Vec::new();
+    let mut coords = vec![0; dims.len()];
+    while !coords.is_empty() {
+        let sum: usize = coords.iter().sum();
+        keyed.push((sum, source.get_color(&coords)));
+        next_coords(&mut coords, dims);
+    }
+
+    keyed.sort_by_key(|(sum, _)| *sum);
+    keyed.into_iter().map(|(_, color)| color).collect()
+}
+
+/// Iterate over colors like the squares of a checkerboard: first those whose coordinates have an
+/// even sum, then those with an odd sum.
+pub fn checkerboard<S: ColorSource>(source: S) -> Vec<Rgb8> {
+    let dims = source.dimensions();
+
+    let mut even = Vec::new();
+    let mut odd = Vec::new();
+    let mut coords = vec![0; dims.len()];
+    while !coords.is_empty() {
+        let sum: usize = coords.iter().sum();
+        if sum.is_multiple_of(2) {
+            even.push(source.get_color(&coords));
+        } else {
+            odd.push(source.get_color(&coords));
+        }
+        next_coords(&mut coords, dims);
+    }
+
+    even.append(&mut odd);
+    even
+}
+
+/// Iterate over colors in Hilbert curve order of their positions in the source.
+pub fn by_source_position<S: HasPositions>(source: S) -> Vec<Rgb8> {
+    let dims = source.dimensions();
+    let bits = log2(*dims.iter().max().unwrap());
+    let side = (1usize << bits) as f64;
+
+    let mut keyed = Vec::new();
+    let mut coords = vec![0; dims.len()];
+    while !coords.is_empty() {
+        let (x, y) = source.position(&coords);
+        let x = ((x * side) as usize).min((side as usize) - 1);
+        let y = ((y * side) as usize).min((side as usize) - 1);
+        let index = hilbert_index(&[x, y], &[bits, bits]);
+        keyed.push((index, source.get_color(&coords)));
+        next_coords(&mut coords, dims);
+    }
+
+    keyed.sort_by_key(|(index, _)| *index);
+    keyed.into_iter().map(|(_, color)| color).collect()
+}
+
+/// Measure the locality of an ordered list of colors.
+///
+/// This applies [path_locality] to their L\*a\*b\* coordinates, and averages the distances over
+/// every offset, so smaller is better.
+fn locality(colors: &[Rgb8]) -> f64 {
+    let points = colors.iter().map(|&c| LabSpace::from(c).0);
+    let locality = path_locality(points, colors.len());
+    if locality.is_empty() {
+        return 0.0;
+    }
+
+    let total: f64 = locality.iter().map(|(_, d)| d).sum();
+    total / locality.len() as f64
+}
+
+/// Iterate over colors in whichever of hue, Morton, or Hilbert order has the best locality.
+pub fn best_curve_order<S: ColorSource>(source: S) -> Vec<Rgb8> {
+    let candidates = [morton(&source), hilbert(&source)];
+
+    let mut best = hue_sorted(&source);
+    let mut best_score = locality(&best);
+    for colors in candidates {
+        let score = locality(&colors);
+        if score < best_score {
+            best = colors;
+            best_score = score;
+        }
+    }
+
+    best
+}
+
+/// An iterator over a list of colors in striped order, to reduce artifacts in the generated image.
 ///
 /// The striped ordering gives every other item first, then every other item from the remaining
 /// items, etc. For example, the striped form of `0..16` is
 /// `[0, 2, 4, 6, 8, 10, 12, 14, 1, 5, 9, 13, 3, 11, 7, 15]`.
+#[derive(Clone, Debug)]
+pub struct StripedIter<'a> {
+    colors: &'a [Rgb8],
+    /// The distance between the items of the current stripe is twice this.
+    stripe: usize,
+    /// The index of the next item.
+    next: usize,
+    /// The number of items left.
+    remaining: usize,
+}
+
+impl<'a> StripedIter<'a> {
+    /// Iterate over some colors in striped order.
+    pub fn new(colors: &'a [Rgb8]) -> Self {
+        Self {
+            colors,
+            stripe: 1,
+            next: 0,
+            remaining: colors.len(),
+        }
+    }
+}
+
+impl Iterator for StripedIter<'_> {
+    type Item = Rgb8;
+
+    fn next(&mut self) -> Option<Rgb8> {
+        let len = self.colors.len();
+
+        while self.stripe <= len {
+            if self.next < len {
+                let color = self.colors[self.next];
+                self.next += 2 * self.stripe;
+                self.remaining -= 1;
+                return Some(color);
+            }
+
+            self.stripe *= 2;
+            self.next = self.stripe - 1;
+        }
+
+        None
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl ExactSizeIterator for StripedIter<'_> {}
+
+/// Stripe an ordered list of colors into a new [Vec].
+///
+/// See [StripedIter] for the ordering, and to stripe colors without a second allocation.
 pub fn striped(colors: Vec<Rgb8>) -> Vec<Rgb8> {
-    let len = colors.len();
-    let mut result = Vec::with_capacity(len);
-    let mut stripe = 1;
-    while stripe <= len {
-        for i in ((stripe - 1)..len).step_by(2 * stripe) {
-            result.push(colors[i]);
+    StripedIter::new(&colors).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::color::source::{AllColors, ImageColors, PaletteColors};
+
+    use image::RgbImage;
+
+    use rand::prelude::*;
+    use rand_pcg::Pcg64;
+
+    use std::collections::HashSet;
+
+    #[test]
+    fn test_hue_sorted() {
+        let colors = hue_sorted(AllColors::new(3, 3, 3));
+        assert_eq!(colors.len(), 512);
+        assert!(colors.windows(2).all(|w| Hue::from(w[0]) <= Hue::from(w[1])));
+
+        // Grays have no hue, so they are sorted by luminance
+        let grays = PaletteColors::from(vec![
+            Rgb8::from([200, 200, 200]),
+            Rgb8::from([128, 128, 128]),
+            Rgb8::from([0, 0, 0]),
+        ]);
+        let grays: Vec<_> = hue_sorted(grays).into_iter().map(|c| c.0).collect();
+        assert_eq!(grays, [[0, 0, 0], [128, 128, 128], [200, 200, 200]]);
+    }
+
+    #[test]
+    fn test_saturation_sorted() {
+        let colors = saturation_sorted(AllColors::new(2, 2, 2));
+        assert_eq!(colors.len(), 64);
+
+        let saturated = colors.iter().position(|c| *c == Rgb8::from([192, 0, 0])).unwrap();
+        let grays = [[0, 0, 0], [64, 64, 64], [128, 128, 128], [192, 192, 192]];
+        for gray in grays {
+            let gray = colors.iter().position(|c| c.0 == gray).unwrap();
+            assert!(saturated < gray);
+            assert!(gray >= 60);
+        }
+
+        // Grays are sorted by lightness
+        assert_eq!(&colors[60..].iter().map(|c| c.0).collect::<Vec<_>>(), &grays);
+    }
+
+    #[test]
+    fn test_luminance_sorted() {
+        let colors = luminance_sorted(AllColors::new(3, 3, 3));
+        assert_eq!(colors.len(), 512);
+        assert_eq!(colors[0], Rgb8::from([0, 0, 0]));
+        assert_eq!(colors[511], Rgb8::from([224, 224, 224]));
+
+        // Green contributes the most to luminance, and blue the least
+        let index = |rgb| colors.iter().position(|c| *c == Rgb8::from(rgb)).unwrap();
+        assert!(index([0, 0, 224]) < index([224, 0, 0]));
+        assert!(index([224, 0, 0]) < index([0, 224, 0]));
+    }
+
+    #[test]
+    fn test_sample_random() {
+        let mut rng = Pcg64::seed_from_u64(0);
+        let all = AllColors::new(8, 8, 8);
+        assert_eq!(all.total_count(), 1 << 24);
+
+        let sample = sample_random(&all, 100, &mut rng);
+        assert_eq!(sample.len(), 100);
+        let distinct: HashSet<_> = sample.iter().map(|c| c.0).collect();
+        assert_eq!(distinct.len(), 100);
+
+        // Asking for too many colors gives all of them
+        let small = AllColors::new(2, 2, 2);
+        let mut sample = sample_random(&small, 100, &mut rng);
+        sample.sort_by_key(|c| c.0);
+        let mut expected: Vec<_> = ColorSourceIter::from(&small).collect();
+        expected.sort_by_key(|c| c.0);
+        assert_eq!(sample, expected);
+    }
+
+    #[test]
+    fn test_striped() {
+        let colors: Vec<_> = (0..16).map(|i| Rgb8::from([i, 0, 0])).collect();
+        let iter = StripedIter::new(&colors);
+        assert_eq!(iter.len(), 16);
+        let order: Vec<_> = iter.map(|c| c[0]).collect();
+        assert_eq!(order, [0, 2, 4, 6, 8, 10, 12, 14, 1, 5, 9, 13, 3, 11, 7, 15]);
+
+        // Every color is visited exactly once, whatever the length
+        for len in 0..100 {
+            let colors: Vec<_> = (0..len).map(|i| Rgb8::from([i, 0, 0])).collect();
+            let mut order: Vec<_> = striped(colors).into_iter().map(|c| c[0]).collect();
+            order.sort();
+            assert_eq!(order, (0..len).collect::<Vec<_>>());
+        }
+    }
+
+    #[test]
+    fn test_morton3d() {
+        assert_eq!(compact_by_3(0b101_010_111), 0b101);
+        assert_eq!(compact_by_3(u64::MAX), 0x1f_ffff);
+
+        let all = AllColors::new(4, 4, 4);
+        assert_eq!(morton3d(&all), morton(&all));
+
+        let all = AllColors::new(2, 3, 1);
+        assert_eq!(morton3d(&all), morton(&all));
+
+        let img = RgbImage::from_fn(3, 5, |x, y| Rgb8::from([x as u8, y as u8, 0]));
+        assert_eq!(morton3d(ImageColors::from(img.clone())), morton(ImageColors::from(img)));
+    }
+
+    #[test]
+    fn test_hilbert3d() {
+        let colors = hilbert3d(AllColors::new(8, 8, 8));
+        assert_eq!(colors.len(), 1 << 24);
+
+        let mut seen = vec![false; 1 << 24];
+        for c in &colors {
+            let i = (usize::from(c[0]) << 16) | (usize::from(c[1]) << 8) | usize::from(c[2]);
+            assert!(!seen[i], "{:?}", c);
+            seen[i] = true;
+        }
+
+        // Consecutive colors are adjacent in the cube
+        let step = |a: u8, b: u8| a.abs_diff(b) as u32;
+        assert!(colors
+            .windows(2)
+            .all(|w| (0..3).map(|i| step(w[0][i], w[1][i])).sum::<u32>() == 1));
+
+        let all = AllColors::new(2, 2, 2);
+        assert_eq!(hilbert3d(&all), hilbert(&all));
+
+        // 2D sources fall back to the compact Hilbert curve
+        let img = RgbImage::from_fn(3, 5, |x, y| Rgb8::from([x as u8, y as u8, 0]));
+        assert_eq!(hilbert3d(ImageColors::from(img.clone())), hilbert(ImageColors::from(img)));
+    }
+
+    #[test]
+    fn test_moore() {
+        let img = RgbImage::from_fn(4, 4, |x, y| Rgb8::from([x as u8, y as u8, 0]));
+        let colors = moore(ImageColors::from(img));
+        let points: Vec<_> = colors.iter().map(|c| (c[0], c[1])).collect();
+        assert_eq!(&points[..4], &[(1, 0), (0, 0), (0, 1), (1, 1)]);
+        assert_eq!(points[15], (2, 0));
+
+        // Non-square sources fall back to the Hilbert curve
+        let all = AllColors::new(2, 2, 2);
+        assert_eq!(moore(&all), hilbert(&all));
+    }
+
+    #[test]
+    fn test_peano() {
+        let img = RgbImage::from_fn(3, 3, |x, y| Rgb8::from([x as u8, y as u8, 0]));
+        let colors = peano(ImageColors::from(img));
+        let points: Vec<_> = colors.iter().map(|c| (c[0], c[1])).collect();
+        assert_eq!(
+            points,
+            [(0, 0), (0, 1), (0, 2), (1, 2), (1, 1), (1, 0), (2, 0), (2, 1), (2, 2)],
+        );
+
+        assert_eq!(peano(AllColors::new(2, 2, 2)).len(), 64);
+    }
+
+    #[test]
+    fn test_diagonal() {
+        let img = RgbImage::from_fn(4, 4, |x, y| Rgb8::from([x as u8, y as u8, 0]));
+        let colors = diagonal(ImageColors::from(img));
+        let points: Vec<_> = colors.iter().map(|c| (c[0], c[1])).collect();
+
+        #[rustfmt::skip]
+        let expected = [
+            (0, 0),
+            (1, 0), (0, 1),
+            (2, 0), (1, 1), (0, 2),
+            (3, 0), (2, 1), (1, 2), (0, 3),
+            (3, 1), (2, 2), (1, 3),
+            (3, 2), (2, 3),
+            (3, 3),
+        ];
+        assert_eq!(points, expected);
+    }
+
+    #[test]
+    fn test_checkerboard() {
+        let img = RgbImage::from_fn(5, 3, |x, y| Rgb8::from([x as u8, y as u8, 0]));
+        let colors = checkerboard(ImageColors::from(img));
+        assert_eq!(colors.len(), 15);
+
+        let parities: Vec<_> = colors.iter().map(|c| (c[0] + c[1]) % 2).collect();
+        assert_eq!(parities.iter().filter(|p| **p == 0).count(), 8);
+        assert!(parities[..8].iter().all(|p| *p == 0));
+        assert!(parities[8..].iter().all(|p| *p == 1));
+    }
+
+    #[test]
+    fn test_van_der_corput() {
+        let octants = |colors: &[Rgb8]| {
+            let mut octants: Vec<_> = colors
+                .iter()
+                .map(|c| (c[0] >= 128, c[1] >= 128, c[2] >= 128))
+                .collect();
+            octants.sort();
+            octants.dedup();
+            octants.len()
+        };
+
+        // An 8-color palette has one color per octant, and the first 8 visit all of them
+        let colors = van_der_corput(AllColors::new(1, 1, 1));
+        assert_eq!(colors.len(), 8);
+        assert_eq!(octants(&colors[..8]), 8);
+
+        let colors = van_der_corput(AllColors::new(2, 2, 2));
+        assert_eq!(colors.len(), 64);
+        for chunk in colors.chunks(8) {
+            assert_eq!(octants(chunk), 8);
         }
-        stripe *= 2;
     }
 
-    result
+    #[test]
+    fn test_by_source_position() {
+        let img = RgbImage::from_fn(4, 4, |x, y| Rgb8::from([16 * x as u8, 16 * y as u8, 0]));
+        let source = ImageColors::from(img);
+
+        let colors = by_source_position(&source);
+        assert_eq!(colors.len(), 16);
+        assert_eq!(colors, hilbert(&source));
+    }
+
+    #[test]
+    fn test_by_frequency_then_hue() {
+        let img = RgbImage::from_fn(4, 4, |x, y| match (x, y) {
+            (0, _) => Rgb8::from([0, 0, 255]),
+            (_, 0) => Rgb8::from([0, 255, 0]),
+            (1, 1) => Rgb8::from([255, 0, 0]),
+            _ => Rgb8::from([255, 255, 0]),
+        });
+
+        assert_eq!(
+            by_frequency_then_hue(ImageColors::from(img)),
+            vec![
+                Rgb8::from([255, 255, 0]),
+                Rgb8::from([0, 0, 255]),
+                Rgb8::from([0, 255, 0]),
+                Rgb8::from([255, 0, 0]),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_best_curve_order_monochrome() {
+        // The curve orders follow the palette's own order, which is shuffled here, but sorting
+        // the shades of red by hue (then luminance) puts them back in order
+        let mut rng = Pcg64::seed_from_u64(0);
+        let mut colors: Vec<_> = (0..=255).map(|r| Rgb8::from([r, 0, 0])).collect();
+        colors.shuffle(&mut rng);
+        let source = PaletteColors::from(colors);
+
+        let best = best_curve_order(&source);
+        assert_ne!(best, morton(&source));
+        assert_ne!(best, hilbert(&source));
+        assert_eq!(best, hue_sorted(&source));
+    }
+
+    #[test]
+    fn test_best_curve_order_all_colors() {
+        // Across the whole RGB cube, the Hilbert curve keeps consecutive colors the closest
+        let source = AllColors::new(4, 4, 4);
+
+        let best = best_curve_order(&source);
+        assert_ne!(best, hue_sorted(&source));
+        assert_ne!(best, morton(&source));
+        assert_eq!(best, hilbert(&source));
+    }
+
+    #[test]
+    fn test_best_curve_order_random() {
+        let mut rng = Pcg64::seed_from_u64(0);
+        let colors: Vec<_> = (0..100).map(|_| Rgb8::from(rng.gen::<[u8; 3]>())).collect();
+        let source = PaletteColors::from(colors);
+        assert_eq!(best_curve_order(&source).len(), 100);
+    }
 }
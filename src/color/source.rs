@@ -1,9 +1,13 @@
 //! Sources of colors.
 
-use super::Rgb8;
+use super::order::next_coords;
+use super::{ColorSpace, HslSpace, LabSpace, Rgb8};
 
 use image::RgbImage;
 
+use std::collections::HashSet;
+use std::f64::consts::TAU;
+
 /// A source of colors in multidimensional space.
 pub trait ColorSource {
     /// Get the size of each dimension in this space.
@@ -11,6 +15,43 @@ pub trait ColorSource {
 
     /// Get the color at some particular coordinates.
     fn get_color(&self, coords: &[usize]) -> Rgb8;
+
+    /// Get the total number of colors in this space.
+    fn total_count(&self) -> usize {
+        self.dimensions().iter().product()
+    }
+
+    /// Skip any repeated colors in this source, keeping only the first of each.
+    fn deduplicated(self) -> Deduplicated<Self>
+    where
+        Self: Sized,
+    {
+        Deduplicated::new(self)
+    }
+}
+
+/// A [ColorSource] whose colors have a position in 2D space, e.g. an image.
+pub trait HasPositions: ColorSource {
+    /// Get the position of the color at some coordinates, normalized to [0, 1].
+    fn position(&self, coords: &[usize]) -> (f64, f64);
+}
+
+/// Blanket [ColorSource] implementation for references.
+impl<S: ColorSource> ColorSource for &S {
+    fn dimensions(&self) -> &[usize] {
+        (*self).dimensions()
+    }
+
+    fn get_color(&self, coords: &[usize]) -> Rgb8 {
+        (*self).get_color(coords)
+    }
+}
+
+/// Blanket [HasPositions] implementation for references.
+impl<S: HasPositions> HasPositions for &S {
+    fn position(&self, coords: &[usize]) -> (f64, f64) {
+        (*self).position(coords)
+    }
 }
 
 /// The entire RGB space.
@@ -30,6 +71,72 @@ impl AllColors {
     }
 }
 
+/// The minimum number of levels per channel that [AllColors::n_most_distinct()] searches.
+const DISTINCT_LEVELS: usize = 32;
+
+impl AllColors {
+    /// Pick `n` colors from this source that are spread out as far as possible in the color space
+    /// `C`, using [farthest-point sampling](https://en.wikipedia.org/wiki/Farthest-first_traversal).
+    ///
+    /// Starting from black, each color picked is the one farthest from all the colors picked so
+    /// far.  To bound the cost, the candidates are limited to an evenly spaced grid (including the
+    /// extremes of each channel) with 32 levels per channel, or enough for `8 * n` colors if that's
+    /// more.  This takes `O(n)` passes over the grid.
+    pub fn n_most_distinct<C: ColorSpace>(&self, n: usize) -> Vec<Rgb8>
+    where
+        C::Value: PartialOrd<C::Distance>,
+    {
+        let levels = ((8.0 * n as f64).cbrt().ceil() as usize).max(DISTINCT_LEVELS);
+        let [rs, gs, bs] = self.dims.map(|dim| {
+            let k = levels.min(dim);
+            if k > 1 {
+                (0..k).map(|i| i * (dim - 1) / (k - 1)).collect()
+            } else {
+                vec![0]
+            }
+        });
+
+        let mut candidates = Vec::with_capacity(rs.len() * gs.len() * bs.len());
+        for &r in &rs {
+            for &g in &gs {
+                for &b in &bs {
+                    let rgb8 = self.get_color(&[r, g, b]);
+                    candidates.push((rgb8, C::from(rgb8)));
+                }
+            }
+        }
+
+        let mut picked = Vec::with_capacity(n);
+        let mut min_distances: Vec<Option<C::Distance>> = vec![None; candidates.len()];
+        let mut next = 0;
+
+        while picked.len() < n.min(candidates.len()) {
+            let (rgb8, color) = candidates[next];
+            picked.push(rgb8);
+
+            let mut farthest = None;
+            for (i, (_, candidate)) in candidates.iter().enumerate() {
+                let distance = color.distance(candidate);
+                let min = match min_distances[i] {
+                    Some(min) if min <= distance => min,
+                    _ => {
+                        min_distances[i] = Some(distance);
+                        distance
+                    }
+                };
+
+                if farthest.is_none_or(|(_, max)| min > max) {
+                    farthest = Some((i, min));
+                }
+            }
+
+            next = farthest.unwrap().0;
+        }
+
+        picked
+    }
+}
+
 impl ColorSource for AllColors {
     fn dimensions(&self) -> &[usize] {
         &self.dims
@@ -44,6 +151,48 @@ impl ColorSource for AllColors {
     }
 }
 
+/// A fixed list of colors.
+#[derive(Debug)]
+pub struct PaletteColors {
+    dims: [usize; 1],
+    colors: Vec<Rgb8>,
+}
+
+impl From<Vec<Rgb8>> for PaletteColors {
+    fn from(colors: Vec<Rgb8>) -> Self {
+        Self {
+            dims: [colors.len()],
+            colors,
+        }
+    }
+}
+
+/// Take the palette from an image, one color per pixel.
+impl From<RgbImage> for PaletteColors {
+    fn from(image: RgbImage) -> Self {
+        image.pixels().copied().collect::<Vec<_>>().into()
+    }
+}
+
+impl PaletteColors {
+    /// Check whether every color in this palette is different.
+    pub fn is_distinct(&self) -> bool {
+        let mut colors: Vec<_> = self.colors.iter().map(|c| c.0).collect();
+        colors.sort_unstable();
+        colors.windows(2).all(|w| w[0] != w[1])
+    }
+}
+
+impl ColorSource for PaletteColors {
+    fn dimensions(&self) -> &[usize] {
+        &self.dims
+    }
+
+    fn get_color(&self, coords: &[usize]) -> Rgb8 {
+        self.colors[coords[0]]
+    }
+}
+
 /// Colors extracted from an image.
 #[derive(Debug)]
 pub struct ImageColors {
@@ -69,3 +218,332 @@ impl ColorSource for ImageColors {
         *self.image.get_pixel(coords[0] as u32, coords[1] as u32)
     }
 }
+
+impl HasPositions for ImageColors {
+    fn position(&self, coords: &[usize]) -> (f64, f64) {
+        (
+            coords[0] as f64 / self.dims[0] as f64,
+            coords[1] as f64 / self.dims[1] as f64,
+        )
+    }
+}
+
+/// A [ColorSource] adapter that skips repeated colors.
+#[derive(Debug)]
+pub struct Deduplicated<S> {
+    source: S,
+    /// The source coordinates of the first occurrence of each color.
+    coords: Vec<Vec<usize>>,
+    dims: [usize; 1],
+}
+
+impl<S: ColorSource> Deduplicated<S> {
+    /// Find the distinct colors of a source.
+    pub fn new(source: S) -> Self {
+        let dims = source.dimensions().to_vec();
+        let mut seen = HashSet::new();
+        let mut coords = Vec::new();
+
+        // An empty source has no coordinates to start from
+        let mut next = if dims.contains(&0) {
+            Vec::new()
+        } else {
+            vec![0; dims.len()]
+        };
+        while !next.is_empty() {
+            if seen.insert(source.get_color(&next).0) {
+                coords.push(next.clone());
+            }
+            next_coords(&mut next, &dims);
+        }
+
+        Self {
+            source,
+            dims: [coords.len()],
+            coords,
+        }
+    }
+}
+
+impl<S: ColorSource> ColorSource for Deduplicated<S> {
+    fn dimensions(&self) -> &[usize] {
+        &self.dims
+    }
+
+    fn get_color(&self, coords: &[usize]) -> Rgb8 {
+        self.source.get_color(&self.coords[coords[0]])
+    }
+}
+
+impl<S: HasPositions> HasPositions for Deduplicated<S> {
+    fn position(&self, coords: &[usize]) -> (f64, f64) {
+        self.source.position(&self.coords[coords[0]])
+    }
+}
+
+/// A smooth ramp of colors between two endpoints.
+#[derive(Debug)]
+pub struct GradientColors {
+    dims: [usize; 1],
+    from: Rgb8,
+    to: Rgb8,
+    lab: bool,
+}
+
+impl GradientColors {
+    /// Create a GradientColors source with `n` colors from `from` to `to`, interpolated in RGB.
+    pub fn new(from: Rgb8, to: Rgb8, n: usize) -> Self {
+        Self {
+            dims: [n],
+            from,
+            to,
+            lab: false,
+        }
+    }
+
+    /// Interpolate in L\*a\*b\* space instead, for a more perceptually even ramp.
+    pub fn in_lab(mut self) -> Self {
+        self.lab = true;
+        self
+    }
+
+    /// Get how far along the gradient the `i`th color is, in the range [0, 1].
+    fn fraction(&self, i: usize) -> f64 {
+        let n = self.dims[0];
+        if n > 1 {
+            i as f64 / (n - 1) as f64
+        } else {
+            0.0
+        }
+    }
+}
+
+impl ColorSource for GradientColors {
+    fn dimensions(&self) -> &[usize] {
+        &self.dims
+    }
+
+    fn get_color(&self, coords: &[usize]) -> Rgb8 {
+        let t = self.fraction(coords[0]);
+
+        if self.lab {
+            let [from, to] = [self.from, self.to].map(LabSpace::from);
+            from.lerp(to, t).to_rgb8()
+        } else {
+            let [from, to] = [self.from, self.to].map(|c| c.0.map(f64::from));
+            Rgb8::from([0, 1, 2].map(|i| (from[i] + t * (to[i] - from[i])).round() as u8))
+        }
+    }
+}
+
+/// The fractional part of the golden ratio, 1/φ.
+const GOLDEN_RATIO_CONJUGATE: f64 = 0.618_033_988_749_895;
+
+/// A palette of colors with hues spaced by the golden ratio.
+///
+/// The hue of the `i`th color is `i / φ (mod 1)`, which spreads the hues as evenly as possible for
+/// any prefix of the palette.
+#[derive(Debug)]
+pub struct GoldenRatioColors {
+    dims: [usize; 1],
+    saturation: f64,
+    lightness: f64,
+}
+
+impl GoldenRatioColors {
+    /// Create a GoldenRatioColors source with `n` colors of the given saturation and lightness.
+    pub fn new(n: usize, saturation: f64, lightness: f64) -> Self {
+        Self {
+            dims: [n],
+            saturation,
+            lightness,
+        }
+    }
+
+    /// Get the hue of the `i`th color, in the range [0, 1].
+    fn hue(&self, i: usize) -> f64 {
+        (i as f64 * GOLDEN_RATIO_CONJUGATE).fract()
+    }
+}
+
+impl ColorSource for GoldenRatioColors {
+    fn dimensions(&self) -> &[usize] {
+        &self.dims
+    }
+
+    fn get_color(&self, coords: &[usize]) -> Rgb8 {
+        HslSpace([TAU * self.hue(coords[0]), self.saturation, self.lightness]).to_rgb8()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::color::{order, RgbSpace};
+    use crate::frontier::min::MinFrontier;
+    use crate::frontier::{Connectivity, Frontier};
+
+    use acap::distance::{Distance, Proximity};
+
+    use rand::SeedableRng;
+    use rand_pcg::Pcg64;
+
+    #[test]
+    fn test_n_most_distinct() {
+        let source = AllColors::new(4, 4, 4);
+
+        let pair = source.n_most_distinct::<RgbSpace>(2);
+        assert_eq!(pair, vec![Rgb8::from([0, 0, 0]), Rgb8::from([240, 240, 240])]);
+
+        // Saturated green is even farther from black than white is in L*a*b*
+        let pair = source.n_most_distinct::<LabSpace>(2);
+        assert_eq!(pair, vec![Rgb8::from([0, 0, 0]), Rgb8::from([0, 240, 0])]);
+
+        // Each color is farther from the previous ones than the next color is
+        let eight: Vec<_> = source
+            .n_most_distinct::<LabSpace>(8)
+            .into_iter()
+            .map(LabSpace::from)
+            .collect();
+        let spacing: Vec<_> = (1..8)
+            .map(|i| {
+                eight[..i]
+                    .iter()
+                    .map(|c| c.distance(&eight[i]).value())
+                    .fold(f64::INFINITY, f64::min)
+            })
+            .collect();
+        for pair in spacing.windows(2) {
+            assert!(pair[0] >= pair[1], "{:?}", spacing);
+        }
+        assert!(spacing[6] > 0.0);
+    }
+
+    #[test]
+    fn test_n_most_distinct_octants() {
+        // The full source is cut down to a coarser grid, which still reaches white
+        let source = AllColors::new(8, 8, 8);
+        let pair = source.n_most_distinct::<RgbSpace>(2);
+        assert_eq!(pair, vec![Rgb8::from([0, 0, 0]), Rgb8::from([255, 255, 255])]);
+
+        // Octants of L*a*b* space, split at L* = 50 and a* = b* = 0
+        let mut octants = [0; 8];
+        for rgb8 in source.n_most_distinct::<LabSpace>(8) {
+            let lab = LabSpace::from(rgb8);
+            let octant = usize::from(lab[0] >= 50.0) << 2
+                | usize::from(lab[1] >= 0.0) << 1
+                | usize::from(lab[2] >= 0.0);
+            octants[octant] += 1;
+        }
+
+        // Greedy sampling from black doesn't reach every octant, but it doesn't crowd any of them
+        assert!(octants.iter().filter(|&&n| n > 0).count() >= 6, "{:?}", octants);
+        assert!(octants.iter().all(|&n| n <= 2), "{:?}", octants);
+    }
+
+    #[test]
+    fn test_golden_ratio_hue_steps() {
+        let source = GoldenRatioColors::new(8, 1.0, 0.5);
+        for i in 1..8 {
+            let prev = HslSpace::from(source.get_color(&[i - 1]))[0] / TAU;
+            let next = HslSpace::from(source.get_color(&[i]))[0] / TAU;
+            let step = (next - prev).rem_euclid(1.0);
+            assert!((step - GOLDEN_RATIO_CONJUGATE).abs() < 0.01, "step {} is {}", i, step);
+        }
+    }
+
+    #[test]
+    fn test_golden_ratio_distinct() {
+        let source = GoldenRatioColors::new(100, 1.0, 0.5);
+
+        let mut hues: Vec<_> = (0..8).map(|i| source.hue(i)).collect();
+        hues.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        hues.dedup();
+        assert_eq!(hues.len(), 8);
+
+        let mut colors: Vec<_> = (0..100).map(|i| source.get_color(&[i]).0).collect();
+        colors.sort();
+        colors.dedup();
+        assert_eq!(colors.len(), 100);
+    }
+
+    #[test]
+    fn test_palette_image() {
+        let image = RgbImage::from_fn(4, 4, |x, y| Rgb8::from([64 * x as u8, 64 * y as u8, 128]));
+        let source = PaletteColors::from(image);
+        assert_eq!(source.dimensions(), &[16]);
+        assert!(source.is_distinct());
+
+        let rng = Pcg64::seed_from_u64(0);
+        let seeds = vec![(2, 2)];
+        let mut frontier = MinFrontier::<LabSpace, _>::new(rng, 4, 4, seeds, Connectivity::Eight);
+        let mut placed: Vec<_> = order::hue_sorted(&source)
+            .into_iter()
+            .map(|rgb8| frontier.place(rgb8).map(|_| rgb8.0))
+            .collect::<Option<_>>()
+            .unwrap();
+        assert_eq!(frontier.place(Rgb8::from([0, 0, 0])), None);
+
+        let mut expected: Vec<_> = (0..16).map(|i| source.get_color(&[i]).0).collect();
+        placed.sort();
+        expected.sort();
+        assert_eq!(placed, expected);
+
+        assert!(!PaletteColors::from(RgbImage::new(2, 1)).is_distinct());
+    }
+
+    #[test]
+    fn test_deduplicated() {
+        // Four distinct colors, each repeated four times
+        let image = RgbImage::from_fn(4, 4, |x, y| Rgb8::from([x as u8 % 2, y as u8 % 2, 0]));
+        let source = ImageColors::from(image).deduplicated();
+        assert_eq!(source.total_count(), 4);
+
+        let colors: Vec<_> = (0..4).map(|i| source.get_color(&[i]).0).collect();
+        assert_eq!(colors, [[0, 0, 0], [1, 0, 0], [0, 1, 0], [1, 1, 0]]);
+
+        // Positions come from the first occurrence of each color
+        assert_eq!(source.position(&[3]), (0.25, 0.25));
+
+        assert_eq!(AllColors::new(2, 2, 2).deduplicated().total_count(), 64);
+
+        // Empty sources stay empty
+        let empty = PaletteColors::from(Vec::new()).deduplicated();
+        assert_eq!(empty.dimensions(), [0]);
+        let empty = ImageColors::from(RgbImage::new(0, 4)).deduplicated();
+        assert_eq!(empty.dimensions(), [0]);
+    }
+
+    #[test]
+    fn test_gradient() {
+        let black = Rgb8::from([0, 0, 0]);
+        let white = Rgb8::from([255, 255, 255]);
+        let red = Rgb8::from([255, 0, 0]);
+        let blue = Rgb8::from([0, 0, 255]);
+
+        let source = GradientColors::new(black, white, 3);
+        assert_eq!(source.dimensions(), &[3]);
+        assert_eq!(source.get_color(&[0]), black);
+        assert_eq!(source.get_color(&[1]), Rgb8::from([128, 128, 128]));
+        assert_eq!(source.get_color(&[2]), white);
+
+        let source = GradientColors::new(red, blue, 256).in_lab();
+        assert_eq!(source.get_color(&[0]), red);
+        assert_eq!(source.get_color(&[255]), blue);
+
+        // Each step is about the same size in L*a*b*
+        let steps: Vec<_> = (1..256)
+            .map(|i| {
+                let prev = LabSpace::from(source.get_color(&[i - 1]));
+                let next = LabSpace::from(source.get_color(&[i]));
+                prev.distance(&next).value()
+            })
+            .collect();
+        let max = steps.iter().copied().fold(0.0, f64::max);
+        assert!(max < 2.0, "{:?}", steps);
+
+        let single = GradientColors::new(red, blue, 1);
+        assert_eq!(single.get_color(&[0]), red);
+    }
+}
@@ -2,10 +2,14 @@
 
 use acap::distance::Proximity;
 use acap::kd::FlatKdTree;
-use acap::knn::{NearestNeighbors, Neighborhood};
+use acap::knn::{NearestNeighbors, Neighbor, Neighborhood};
 use acap::vp::FlatVpTree;
 
+use std::cell::Cell;
+use std::cmp::Ordering;
+use std::fmt::{self, Debug, Formatter};
 use std::iter;
+use std::mem;
 
 /// A trait for objects that can be soft-deleted.
 pub trait SoftDelete {
@@ -14,28 +18,29 @@ pub trait SoftDelete {
 }
 
 /// Blanket [SoftDelete] implementation for references.
-impl<'a, T: SoftDelete> SoftDelete for &'a T {
+impl<T: SoftDelete> SoftDelete for &T {
     fn is_deleted(&self) -> bool {
         (*self).is_deleted()
     }
 }
 
-/// The number of bits dedicated to the flat buffer.
-const BUFFER_BITS: usize = 6;
-/// The maximum size of the buffer.
-const BUFFER_SIZE: usize = 1 << BUFFER_BITS;
+/// The default number of bits dedicated to the flat buffer.
+const DEFAULT_BUFFER_BITS: usize = 6;
 
 /// A dynamic wrapper for a static nearest neighbor search data structure.
 ///
 /// This type applies [dynamization](https://en.wikipedia.org/wiki/Dynamization) to an arbitrary
 /// nearest neighbor search structure `T`, allowing new items to be added dynamically.  It also
 /// implements [soft deletion](https://en.wiktionary.org/wiki/soft_deletion) for dynamic removal.
-#[derive(Debug)]
 pub struct Forest<T: IntoIterator> {
     /// A flat buffer used for the first few items, to avoid repeatedly rebuilding small trees.
     buffer: Vec<T::Item>,
     /// The trees of the forest, with sizes in geometric progression.
     trees: Vec<Option<T>>,
+    /// The number of deleted items each tree has encountered during searches.
+    deleted_hits: Vec<Cell<usize>>,
+    /// The number of bits dedicated to the buffer, i.e. the log of its maximum size.
+    buffer_bits: usize,
 }
 
 impl<T, U> Forest<U>
@@ -45,12 +50,26 @@ where
 {
     /// Create a new empty forest.
     pub fn new() -> Self {
+        Self::with_buffer_bits(DEFAULT_BUFFER_BITS)
+    }
+
+    /// Create a new empty forest, whose buffer holds up to `2^buffer_bits` items.
+    ///
+    /// Small buffers waste less time on linear scans, but build more small trees.
+    pub fn with_buffer_bits(buffer_bits: usize) -> Self {
         Self {
             buffer: Vec::new(),
             trees: Vec::new(),
+            deleted_hits: Vec::new(),
+            buffer_bits,
         }
     }
 
+    /// The maximum size of the buffer.
+    fn buffer_size(&self) -> usize {
+        1 << self.buffer_bits
+    }
+
     /// Add a new item to the forest.
     pub fn push(&mut self, item: T) {
         self.extend(iter::once(item));
@@ -63,6 +82,7 @@ where
 
     /// Drain all items out of the trees and into the buffer.
     fn deforest(&mut self) {
+        self.deleted_hits.clear();
         self.buffer.extend(
             self.trees
                 .drain(..)
@@ -77,13 +97,14 @@ where
         let mut len = self.buffer.len();
 
         for i in 0.. {
-            let bit = 1 << (i + BUFFER_BITS);
+            let bit = 1 << (i + self.buffer_bits);
             if bit > len {
                 break;
             }
 
             if i >= self.trees.len() {
                 self.trees.push(None);
+                self.deleted_hits.push(Cell::new(0));
             }
 
             let tree = self.trees[i].take();
@@ -91,17 +112,19 @@ where
                 (Some(tree), true) => {
                     len += bit;
                     self.buffer.extend(tree.into_iter().filter(|e| !e.is_deleted()));
+                    self.deleted_hits[i].set(0);
                     None
                 }
                 (None, true) => {
                     let offset = self.buffer.len().saturating_sub(bit);
+                    self.deleted_hits[i].set(0);
                     Some(self.buffer.drain(offset..).collect())
                 }
                 (tree, _) => tree,
             }
         }
 
-        debug_assert!(self.buffer.len() < BUFFER_SIZE);
+        debug_assert!(self.buffer.len() < self.buffer_size());
     }
 
     /// Rebuild this index, discarding deleted items.
@@ -110,6 +133,196 @@ where
         self.deforest();
         self.reforest();
     }
+
+    /// Permanently remove the items that match a predicate, along with any soft-deleted items.
+    ///
+    /// Like [Forest::rebuild()], this tears down and rebuilds every tree.
+    pub fn hard_delete<F: Fn(&T) -> bool>(&mut self, predicate: F) {
+        self.deforest();
+        self.buffer.retain(|e| !e.is_deleted() && !predicate(e));
+        self.reforest();
+    }
+
+    /// Release unused memory, like [Vec::shrink_to_fit()].
+    ///
+    /// This drops the empty slots left over for trees that have been emptied by rebuilds.
+    pub fn shrink_to_fit(&mut self) {
+        while let Some(None) = self.trees.last() {
+            self.trees.pop();
+            self.deleted_hits.pop();
+        }
+
+        self.buffer.shrink_to_fit();
+        self.trees.shrink_to_fit();
+        self.deleted_hits.shrink_to_fit();
+    }
+
+    /// A rough estimate of the memory used by the buffer and the tree slots, in bytes.
+    ///
+    /// The contents of the trees themselves are not counted.
+    pub fn memory_usage_estimate(&self) -> usize {
+        self.buffer.capacity() * mem::size_of::<T>()
+            + self.trees.capacity() * mem::size_of::<Option<U>>()
+            + self.deleted_hits.capacity() * mem::size_of::<Cell<usize>>()
+    }
+
+    /// The number of deleted items that searches have skipped over in the current trees.
+    pub fn deleted_hits(&self) -> usize {
+        self.deleted_hits.iter().map(Cell::get).sum()
+    }
+
+    /// Rebuild only the most fragmented tree in this index, discarding its deleted items.
+    ///
+    /// Fragmentation is measured by how many deleted items searches have encountered in each tree,
+    /// relative to its size.  This is much cheaper than a full [Forest::rebuild()], since the
+    /// other trees are left alone (unless the rebuilt items carry over into them).
+    pub fn rebuild_partial(&mut self) {
+        let bits = self.buffer_bits;
+        let worst = self
+            .trees
+            .iter()
+            .zip(&self.deleted_hits)
+            .enumerate()
+            .filter(|(_, (tree, hits))| tree.is_some() && hits.get() > 0)
+            .map(|(i, (_, hits))| (i, hits.get() as f64 / (1usize << (i + bits)) as f64))
+            .max_by(|(_, a), (_, b)| a.total_cmp(b))
+            .map(|(i, _)| i);
+
+        if let Some(i) = worst {
+            let tree = self.trees[i].take().unwrap();
+            self.deleted_hits[i].set(0);
+            self.buffer.extend(tree.into_iter().filter(|e| !e.is_deleted()));
+            self.filter_buffer();
+            self.reforest();
+        }
+    }
+
+    /// Combine the non-deleted items of two trees.
+    fn fuse(a: impl IntoIterator<Item = T>, b: impl IntoIterator<Item = T>) -> Vec<T> {
+        a.into_iter().chain(b).filter(|e| !e.is_deleted()).collect()
+    }
+
+    /// Merge two forests into one.
+    ///
+    /// This is like [Extend::extend()]ing one forest with the items of the other, but trees that
+    /// don't collide with a tree of the same size in the other forest are kept as they are, rather
+    /// than being torn down and rebuilt.  That requires both forests to have the same buffer size;
+    /// otherwise, this falls back to extending one with the other.
+    pub fn merge(mut self, other: Self) -> Self {
+        if other.buffer_bits != self.buffer_bits {
+            self.extend(other);
+            return self;
+        }
+
+        self.buffer.extend(other.buffer);
+        self.filter_buffer();
+
+        let other_len = other.trees.len();
+        let mut others = other.trees.into_iter().zip(other.deleted_hits);
+        let mut carry: Option<Vec<T>> = None;
+
+        for i in 0.. {
+            if i >= self.trees.len() {
+                if i >= other_len && carry.is_none() {
+                    break;
+                }
+                self.trees.push(None);
+                self.deleted_hits.push(Cell::new(0));
+            }
+
+            // Binary addition, where trees of the same size carry into the next slot
+            let (b, b_hits) = others.next().unwrap_or((None, Cell::new(0)));
+            let a = self.trees[i].take();
+            (self.trees[i], carry) = match (a, b, carry) {
+                (a, None, None) => (a, None),
+                (None, Some(b), None) => {
+                    self.deleted_hits[i] = b_hits;
+                    (Some(b), None)
+                }
+                (None, None, Some(c)) => {
+                    self.deleted_hits[i].set(0);
+                    (Some(c.into_iter().collect()), None)
+                }
+                (Some(a), Some(b), None) => {
+                    self.deleted_hits[i].set(0);
+                    (None, Some(Self::fuse(a, b)))
+                }
+                (Some(t), None, Some(c)) | (None, Some(t), Some(c)) => {
+                    self.deleted_hits[i].set(0);
+                    (None, Some(Self::fuse(t, c)))
+                }
+                (Some(a), Some(b), Some(c)) => (Some(a), Some(Self::fuse(b, c))),
+            };
+        }
+
+        if self.buffer.len() >= self.buffer_size() {
+            self.reforest();
+        }
+
+        self
+    }
+}
+
+impl<T, U> Forest<U>
+where
+    T: SoftDelete,
+    U: IntoIterator<Item = T>,
+{
+    /// Iterate over the items in this forest that have not been deleted, without consuming it.
+    pub fn iter_non_deleted<'a>(&'a self) -> impl Iterator<Item = &'a T>
+    where
+        T: 'a,
+        &'a U: IntoIterator<Item = &'a T>,
+    {
+        self.buffer
+            .iter()
+            .chain(self.trees.iter().flatten().flatten())
+            .filter(|e| !e.is_deleted())
+    }
+
+    /// The number of soft-deleted items that are still taking up space in this forest.
+    pub fn pending_deletions<'a>(&'a self) -> usize
+    where
+        T: 'a,
+        &'a U: IntoIterator<Item = &'a T>,
+    {
+        self.buffer
+            .iter()
+            .chain(self.trees.iter().flatten().flatten())
+            .filter(|e| e.is_deleted())
+            .count()
+    }
+
+    /// Find the item furthest from a target.
+    ///
+    /// The trees can't prune a search for the furthest item, so this is a linear scan.
+    pub fn furthest<'a, K>(&'a self, target: &K) -> Option<Neighbor<&'a T, K::Distance>>
+    where
+        T: 'a,
+        &'a U: IntoIterator<Item = &'a T>,
+        K: Proximity<T>,
+    {
+        self.iter_non_deleted()
+            .map(|item| Neighbor::new(item, target.distance(item)))
+            .max_by(|a, b| a.distance.partial_cmp(&b.distance).unwrap_or(Ordering::Equal))
+    }
+}
+
+/// Shows the size of the buffer and each tree, rather than their contents.
+impl<T: IntoIterator> Debug for Forest<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let trees: Vec<_> = self
+            .trees
+            .iter()
+            .enumerate()
+            .map(|(i, tree)| tree.as_ref().map(|_| 1usize << (i + self.buffer_bits)))
+            .collect();
+
+        f.debug_struct("Forest")
+            .field("buffer_len", &self.buffer.len())
+            .field("trees", &trees)
+            .finish()
+    }
 }
 
 impl<T, U> Default for Forest<U>
@@ -130,7 +343,7 @@ where
     fn extend<I: IntoIterator<Item = T>>(&mut self, items: I) {
         self.buffer.extend(items);
 
-        if self.buffer.len() >= BUFFER_SIZE {
+        if self.buffer.len() >= self.buffer_size() {
             self.filter_buffer();
             self.reforest();
         }
@@ -166,30 +379,35 @@ where
 
 /// [Neighborhood] wrapper that ignores soft-deleted items.
 #[derive(Debug)]
-struct SoftNeighborhood<N>(N);
+struct SoftNeighborhood<'a, N> {
+    inner: N,
+    /// Counts the deleted items encountered.
+    deleted_hits: &'a Cell<usize>,
+}
 
-impl<K, V, N> Neighborhood<K, V> for SoftNeighborhood<N>
+impl<K, V, N> Neighborhood<K, V> for SoftNeighborhood<'_, N>
 where
     V: SoftDelete,
     K: Proximity<V>,
     N: Neighborhood<K, V>,
 {
     fn target(&self) -> K {
-        self.0.target()
+        self.inner.target()
     }
 
     fn contains<D>(&self, distance: D) -> bool
     where
         D: PartialOrd<K::Distance>
     {
-        self.0.contains(distance)
+        self.inner.contains(distance)
     }
 
     fn consider(&mut self, item: V) -> K::Distance {
         if item.is_deleted() {
+            self.deleted_hits.set(self.deleted_hits.get() + 1);
             self.target().distance(&item)
         } else {
-            self.0.consider(item)
+            self.inner.consider(item)
         }
     }
 }
@@ -213,13 +431,13 @@ where
             }
         }
 
-        let neighborhood = SoftNeighborhood(neighborhood);
-
         self.trees
             .iter()
-            .flatten()
-            .fold(neighborhood, |n, t| t.search(n))
-            .0
+            .zip(&self.deleted_hits)
+            .filter_map(|(t, hits)| t.as_ref().map(|t| (t, hits)))
+            .fold(neighborhood, |n, (t, deleted_hits)| {
+                t.search(SoftNeighborhood { inner: n, deleted_hits }).inner
+            })
     }
 }
 
@@ -236,7 +454,7 @@ mod tests {
     use acap::coords::Coordinates;
     use acap::euclid::{euclidean_distance, Euclidean, EuclideanDistance};
     use acap::exhaustive::ExhaustiveSearch;
-    use acap::knn::{NearestNeighbors, Neighbor};
+    use acap::knn::NearestNeighbors;
 
     use rand::prelude::*;
 
@@ -245,28 +463,32 @@ mod tests {
     #[derive(Clone, Debug, PartialEq)]
     struct SoftPoint {
         point: [f32; 3],
-        deleted: bool,
+        deleted: Cell<bool>,
     }
 
     impl SoftPoint {
         fn new(x: f32, y: f32, z: f32) -> Self {
             Self {
                 point: [x, y, z],
-                deleted: false,
+                deleted: Cell::new(false),
             }
         }
 
         fn deleted(x: f32, y: f32, z: f32) -> Self {
             Self {
                 point: [x, y, z],
-                deleted: true,
+                deleted: Cell::new(true),
             }
         }
+
+        fn delete(&self) {
+            self.deleted.set(true);
+        }
     }
 
     impl SoftDelete for SoftPoint {
         fn is_deleted(&self) -> bool {
-            self.deleted
+            self.deleted.get()
         }
     }
 
@@ -404,6 +626,244 @@ mod tests {
         test_random_points(&from_iter);
     }
 
+    #[test]
+    fn test_iter_non_deleted() {
+        let mut forest = KdForest::new();
+        for i in 0..100 {
+            let x = i as f32;
+            if i % 3 == 0 {
+                forest.push(SoftPoint::deleted(x, x, x));
+            } else {
+                forest.push(SoftPoint::new(x, x, x));
+            }
+        }
+
+        let mut live: Vec<_> = forest.iter_non_deleted().map(|p| p.point[0]).collect();
+        live.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        let expected: Vec<_> = (0..100).filter(|i| i % 3 != 0).map(|i| i as f32).collect();
+        assert_eq!(live, expected);
+
+        // The forest is still usable afterwards
+        let target = Euclidean([0.0, 0.0, 0.0]);
+        assert_eq!(
+            forest.nearest(&target).expect("No nearest neighbor found").item,
+            &SoftPoint::new(1.0, 1.0, 1.0)
+        );
+        assert_eq!(forest.iter_non_deleted().count(), expected.len());
+    }
+
+    #[test]
+    fn test_rebuild_partial() {
+        let mut forest = KdForest::new();
+        for i in 0..192 {
+            let x = i as f32;
+            forest.push(SoftPoint::new(x, x, x));
+        }
+
+        // The second tree holds the first 128 items, and the first tree holds the rest
+        assert!(forest.buffer.is_empty());
+        assert_eq!(forest.trees.len(), 2);
+
+        for p in forest.trees[0].as_ref().unwrap() {
+            if (p.point[0] as usize).is_multiple_of(2) {
+                p.delete();
+            }
+        }
+        for p in forest.trees[1].as_ref().unwrap() {
+            if (p.point[0] as usize).is_multiple_of(16) {
+                p.delete();
+            }
+        }
+
+        let target = Euclidean([0.0, 0.0, 0.0]);
+        let before: Vec<_> = forest
+            .k_nearest(&target, 192)
+            .into_iter()
+            .map(|n| n.item.clone())
+            .collect();
+        assert_eq!(before.len(), 192 - 32 - 8);
+
+        forest.rebuild_partial();
+
+        // The first tree was more fragmented, so it was drained into the buffer
+        assert_eq!(forest.buffer.len(), 32);
+        assert!(forest.buffer.iter().all(|p| !p.is_deleted()));
+        assert!(forest.trees[0].is_none());
+
+        // The second tree was left alone, deleted items and all
+        let second = forest.trees[1].as_ref().unwrap();
+        assert_eq!(second.iter().count(), 128);
+        assert_eq!(second.iter().filter(|p| p.is_deleted()).count(), 8);
+
+        let after: Vec<_> = forest
+            .k_nearest(&target, 192)
+            .into_iter()
+            .map(|n| n.item.clone())
+            .collect();
+        assert_eq!(after, before);
+    }
+
+    #[test]
+    fn test_hard_delete() {
+        let points: Vec<_> = (0..500)
+            .map(|_| SoftPoint::new(random(), random(), random()))
+            .collect();
+
+        let mut forest: KdForest<_> = points.iter().cloned().collect();
+        let mut rebuilt: KdForest<_> = points.into_iter().collect();
+        assert_eq!(forest.pending_deletions(), 0);
+
+        // Both forests have the same shape, so these delete the same points
+        for p in forest.iter_non_deleted().step_by(4) {
+            p.delete();
+        }
+        for p in rebuilt.iter_non_deleted().step_by(4) {
+            p.delete();
+        }
+        assert_eq!(forest.pending_deletions(), 125);
+        assert_eq!(forest.iter_non_deleted().count(), 375);
+
+        forest.hard_delete(|p| p.deleted.get());
+        rebuilt.rebuild();
+        assert_eq!(forest.pending_deletions(), 0);
+        assert_eq!(rebuilt.pending_deletions(), 0);
+        assert_eq!(forest.iter_non_deleted().count(), 375);
+
+        let target = Euclidean([0.5, 0.5, 0.5]);
+        assert_eq!(forest.k_nearest(&target, 50), rebuilt.k_nearest(&target, 50));
+
+        // The predicate can remove items that were never soft-deleted, alongside ones that were
+        for p in forest.iter_non_deleted().step_by(5) {
+            p.delete();
+        }
+        for p in rebuilt.iter_non_deleted().step_by(5) {
+            p.delete();
+        }
+        for p in rebuilt.iter_non_deleted().filter(|p| p.point[0] < 0.5) {
+            p.delete();
+        }
+        forest.hard_delete(|p| p.point[0] < 0.5);
+        rebuilt.rebuild();
+        assert!(forest.iter_non_deleted().all(|p| p.point[0] >= 0.5));
+        assert_eq!(forest.pending_deletions(), 0);
+        assert_eq!(forest.iter_non_deleted().count(), rebuilt.iter_non_deleted().count());
+        assert_eq!(forest.k_nearest(&target, 50), rebuilt.k_nearest(&target, 50));
+    }
+
+    #[test]
+    fn test_shrink_to_fit() {
+        let mut forest: KdForest<_> = (0..1000)
+            .map(|i| i as f32)
+            .map(|x| SoftPoint::new(x, x, x))
+            .collect();
+        assert_eq!(forest.trees.len(), 4);
+
+        for p in forest.iter_non_deleted() {
+            p.delete();
+        }
+
+        // Searching counts the deleted items, so the trees can be rebuilt one by one
+        let target = Euclidean([0.0, 0.0, 0.0]);
+        assert_eq!(forest.nearest(&target), None);
+        for _ in 0..4 {
+            forest.rebuild_partial();
+        }
+        assert_eq!(forest.trees.len(), 4);
+        assert!(forest.trees.iter().all(Option::is_none));
+
+        let before = forest.memory_usage_estimate();
+        forest.shrink_to_fit();
+        assert_eq!(forest.trees.len(), 0);
+        assert!(forest.memory_usage_estimate() < before);
+        assert_eq!(forest.memory_usage_estimate(), 0);
+    }
+
+    #[test]
+    fn test_furthest() {
+        let mut forest = KdForest::new();
+        for i in 0..100 {
+            forest.push(SoftPoint::new(0.0, 0.0, i as f32));
+        }
+        forest.push(SoftPoint::deleted(0.0, 0.0, 1000.0));
+
+        let target = Euclidean([0.0, 0.0, 0.0]);
+        assert_eq!(
+            forest.furthest(&target).expect("No furthest neighbor found"),
+            Neighbor::new(&SoftPoint::new(0.0, 0.0, 99.0), 99.0)
+        );
+
+        let empty: KdForest<SoftPoint> = KdForest::new();
+        assert_eq!(empty.furthest(&target), None);
+    }
+
+    #[test]
+    fn test_merge() {
+        let mut points = Vec::new();
+        for i in 0..1000 {
+            if i % 5 == 0 {
+                points.push(SoftPoint::deleted(random(), random(), random()));
+            } else {
+                points.push(SoftPoint::new(random(), random(), random()));
+            }
+        }
+
+        let all: KdForest<_> = points.iter().cloned().collect();
+
+        for split in [0, 10, 64, 300, 500, 1000] {
+            let left: KdForest<_> = points[..split].iter().cloned().collect();
+            let right: KdForest<_> = points[split..].iter().cloned().collect();
+            let merged = left.merge(right);
+            assert_eq!(merged.iter_non_deleted().count(), 800);
+
+            let target = Euclidean([random(), random(), random()]);
+            assert_eq!(merged.k_nearest(&target, 10), all.k_nearest(&target, 10));
+        }
+
+        // The right forest's trees are [None, Some(128)], so its first slot is empty but later
+        // slots are not
+        let point = |_| SoftPoint::new(random(), random(), random());
+        let left: KdForest<_> = (0..10).map(point).collect();
+        let right: KdForest<_> = (0..128).map(point).collect();
+        assert_eq!(format!("{:?}", right), "Forest { buffer_len: 0, trees: [None, Some(128)] }");
+        let merged = left.merge(right);
+        assert_eq!(merged.iter_non_deleted().count(), 138);
+    }
+
+    #[test]
+    fn test_buffer_bits() {
+        let forest: KdForest<_> = (0..100)
+            .map(|i| i as f32)
+            .map(|x| SoftPoint::new(x, x, x))
+            .fold(KdForest::with_buffer_bits(2), |mut forest, p| {
+                forest.push(p);
+                forest
+            });
+
+        assert_eq!(
+            format!("{:?}", forest),
+            "Forest { buffer_len: 0, trees: [Some(4), None, None, Some(32), Some(64)] }"
+        );
+
+        test_nearest_neighbors(|points| {
+            let mut forest = KdForest::with_buffer_bits(2);
+            forest.extend(points);
+            forest
+        });
+    }
+
+    #[test]
+    fn test_debug() {
+        let forest: KdForest<_> = (0..300)
+            .map(|i| i as f32)
+            .map(|x| SoftPoint::new(x, x, x))
+            .collect();
+
+        assert_eq!(
+            format!("{:?}", forest),
+            "Forest { buffer_len: 44, trees: [None, None, Some(256)] }"
+        );
+    }
+
     #[test]
     fn test_exhaustive_forest() {
         test_nearest_neighbors(Forest::<ExhaustiveSearch<_>>::from_iter);
@@ -1,16 +1,21 @@
 //! Frontiers on which to place pixels.
 
+pub mod fountain;
 pub mod image;
+pub mod max;
 pub mod mean;
+pub mod median;
 pub mod min;
+pub mod wrap;
 
-use crate::color::Rgb8;
+use crate::color::{ColorSpace, Rgb8};
 use crate::forest::SoftDelete;
 
 use acap::coords::Coordinates;
-use acap::distance::{Proximity, Metric};
+use acap::distance::{Distance, Proximity, Metric};
 
 use std::cell::Cell;
+use std::fmt::{self, Display, Formatter};
 use std::ops::Deref;
 use std::rc::Rc;
 
@@ -25,8 +30,225 @@ pub trait Frontier {
     /// The number of pixels currently on the frontier.
     fn len(&self) -> usize;
 
+    /// Whether the frontier is currently empty.
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
     /// Place the given color on the frontier, and return its position.
     fn place(&mut self, rgb8: Rgb8) -> Option<(u32, u32)>;
+
+    /// Place the given color on the frontier, and return details about the placement.
+    ///
+    /// This is slower than [Frontier::place], so it should only be used for debugging.
+    fn place_debug(&mut self, rgb8: Rgb8) -> Option<Placement>;
+
+    /// Get the current state of a pixel.
+    fn pixel_state(&self, x: u32, y: u32) -> PixelState;
+
+    /// Get the opacity of a pixel, which is fully opaque once a color has been placed on it.
+    fn pixel_alpha(&self, x: u32, y: u32) -> u8 {
+        match self.pixel_state(x, y) {
+            PixelState::Filled => 255,
+            _ => 0,
+        }
+    }
+
+    /// Iterate over the filled pixels and the colors placed on them.
+    fn visited_pixels(&self) -> impl Iterator<Item = (u32, u32, Rgb8)>;
+
+    /// Get statistics about the colors placed so far.
+    ///
+    /// Frontiers that don't keep track of these return empty statistics.
+    fn statistics(&self) -> Statistics {
+        Statistics::default()
+    }
+}
+
+/// Statistics about the colors placed on a frontier.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Statistics {
+    /// The number of pixels placed.
+    pub pixels_placed: usize,
+    /// The largest size the frontier reached.
+    pub max_frontier_size: usize,
+    /// The sum of the distances between each placed color and its nearest frontier pixel.
+    pub total_distance_sum: f64,
+    /// The smallest distance to a nearest frontier pixel.
+    pub min_distance: f64,
+    /// The largest distance to a nearest frontier pixel.
+    pub max_distance: f64,
+    /// The number of placements that had a nearest frontier pixel.
+    distances: usize,
+}
+
+impl Statistics {
+    /// Record a placement, given the distance to the nearest frontier pixel (if there was one) and
+    /// the resulting size of the frontier.
+    fn record(&mut self, distance: Option<f64>, frontier_size: usize) {
+        self.pixels_placed += 1;
+        self.max_frontier_size = self.max_frontier_size.max(frontier_size);
+
+        if let Some(distance) = distance {
+            if self.distances == 0 {
+                self.min_distance = distance;
+                self.max_distance = distance;
+            } else {
+                self.min_distance = self.min_distance.min(distance);
+                self.max_distance = self.max_distance.max(distance);
+            }
+            self.total_distance_sum += distance;
+            self.distances += 1;
+        }
+    }
+
+    /// Add the statistics of another, independent frontier to these.
+    pub fn merge(&mut self, other: &Self) {
+        self.pixels_placed += other.pixels_placed;
+        self.max_frontier_size = self.max_frontier_size.max(other.max_frontier_size);
+
+        if other.distances > 0 {
+            if self.distances == 0 {
+                self.min_distance = other.min_distance;
+                self.max_distance = other.max_distance;
+            } else {
+                self.min_distance = self.min_distance.min(other.min_distance);
+                self.max_distance = self.max_distance.max(other.max_distance);
+            }
+            self.total_distance_sum += other.total_distance_sum;
+            self.distances += other.distances;
+        }
+    }
+
+    /// The average distance to a nearest frontier pixel.
+    pub fn mean_distance(&self) -> f64 {
+        if self.distances == 0 {
+            0.0
+        } else {
+            self.total_distance_sum / self.distances as f64
+        }
+    }
+}
+
+impl Display for Statistics {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Placed {} pixels, max frontier size {}, distance min {:.3} / mean {:.3} / max {:.3}",
+            self.pixels_placed,
+            self.max_frontier_size,
+            self.min_distance,
+            self.mean_distance(),
+            self.max_distance,
+        )
+    }
+}
+
+/// The state of a pixel in the image.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum PixelState {
+    /// The pixel is empty.
+    Empty,
+    /// The pixel is empty, but on the frontier.
+    Frontier,
+    /// The pixel has been filled.
+    Filled,
+}
+
+impl Display for PixelState {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Empty => write!(f, "empty"),
+            Self::Frontier => write!(f, "frontier"),
+            Self::Filled => write!(f, "filled"),
+        }
+    }
+}
+
+/// The frontier pixel closest to a placed color.
+#[derive(Debug)]
+pub struct NearestPixel {
+    /// The position of the pixel.
+    pub pos: (u32, u32),
+    /// The pixel's color, in the frontier's color space.
+    pub color: String,
+    /// The distance between the pixel and the placed color.
+    pub distance: f64,
+}
+
+/// Details about the placement of a color, for debugging.
+#[derive(Debug)]
+pub struct Placement {
+    /// The color that was placed.
+    pub rgb8: Rgb8,
+    /// The placed color, in the frontier's color space.
+    pub color: String,
+    /// The position the color was placed at.
+    pub pos: (u32, u32),
+    /// The closest frontier pixel, if there was one.
+    pub nearest: Option<NearestPixel>,
+    /// The state of each neighboring pixel.
+    pub neighbors: Vec<((u32, u32), PixelState)>,
+}
+
+impl Placement {
+    /// Collect the details of a placement on a frontier.
+    fn new<C, F>(
+        frontier: &F,
+        rgb8: Rgb8,
+        pos: (u32, u32),
+        nearest: Option<((u32, u32), C, C::Distance)>,
+    ) -> Self
+    where
+        C: ColorSpace,
+        C::Value: PartialOrd<C::Distance>,
+        F: Frontier + ?Sized,
+    {
+        let (x, y) = pos;
+        let neighbors = neighbors(x, y)
+            .iter()
+            .filter(|(x, y)| *x < frontier.width() && *y < frontier.height())
+            .map(|&(x, y)| ((x, y), frontier.pixel_state(x, y)))
+            .collect();
+
+        Self {
+            rgb8,
+            color: C::to_display_string(rgb8),
+            pos,
+            nearest: nearest.map(|(pos, color, distance)| NearestPixel {
+                pos,
+                color: color.to_string(),
+                distance: distance.value(),
+            }),
+            neighbors,
+        }
+    }
+}
+
+impl Display for Placement {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let [r, g, b] = self.rgb8.0;
+        writeln!(
+            f,
+            "RGB({}, {}, {}) placed at ({}, {}), color {}",
+            r, g, b, self.pos.0, self.pos.1, self.color,
+        )?;
+
+        match &self.nearest {
+            Some(nearest) => writeln!(
+                f,
+                "  nearest frontier pixel: ({}, {}), color {}, distance {:.3}",
+                nearest.pos.0, nearest.pos.1, nearest.color, nearest.distance,
+            )?,
+            None => writeln!(f, "  nearest frontier pixel: none")?,
+        }
+
+        for ((x, y), state) in &self.neighbors {
+            writeln!(f, "  neighbor ({}, {}): {}", x, y, state)?;
+        }
+
+        Ok(())
+    }
 }
 
 /// A pixel on a frontier.
@@ -161,6 +383,64 @@ impl<C> SoftDelete for RcPixel<C> {
     }
 }
 
+/// Which pixels count as neighbors on a frontier.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum Connectivity {
+    /// Only the four pixels sharing an edge, for diamond-shaped growth.
+    Four,
+    /// All eight surrounding pixels.
+    #[default]
+    Eight,
+}
+
+/// The neighbors of a pixel location, for some [Connectivity].
+#[derive(Debug)]
+struct Neighbors {
+    array: [(u32, u32); 8],
+    len: usize,
+}
+
+impl Deref for Neighbors {
+    type Target = [(u32, u32)];
+
+    fn deref(&self) -> &Self::Target {
+        &self.array[..self.len]
+    }
+}
+
+impl Neighbors {
+    /// Wrap neighbors that fall off the edge of the image around to the other side.
+    fn wrap(mut self, width: u32, height: u32) -> Self {
+        for (x, y) in &mut self.array[..self.len] {
+            *x = if *x == u32::MAX { width - 1 } else { *x % width };
+            *y = if *y == u32::MAX { height - 1 } else { *y % height };
+        }
+        self
+    }
+}
+
+impl Connectivity {
+    /// Return the neighbors of a pixel location.
+    fn neighbors(self, x: u32, y: u32) -> Neighbors {
+        let all = neighbors(x, y);
+
+        match self {
+            Self::Four => {
+                let mut array = [(0, 0); 8];
+                let mut len = 0;
+                for (nx, ny) in all {
+                    if nx == x || ny == y {
+                        array[len] = (nx, ny);
+                        len += 1;
+                    }
+                }
+                Neighbors { array, len }
+            }
+            Self::Eight => Neighbors { array: all, len: 8 },
+        }
+    }
+}
+
 /// Return all the neighbors of a pixel location.
 fn neighbors(x: u32, y: u32) -> [(u32, u32); 8] {
     let xm1 = x.wrapping_sub(1);
@@ -179,3 +459,185 @@ fn neighbors(x: u32, y: u32) -> [(u32, u32); 8] {
         (xp1, yp1),
     ]
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use super::image::ImageFrontier;
+    use super::mean::MeanFrontier;
+    use super::min::MinFrontier;
+
+    use crate::color::LabSpace;
+
+    use ::image::{Rgba, RgbaImage};
+
+    use rand::SeedableRng;
+    use rand_pcg::Pcg64;
+
+    use std::collections::HashSet;
+
+    #[test]
+    fn test_place_debug() {
+        let rng = Pcg64::seed_from_u64(0);
+        let seeds = vec![(0, 0)];
+        let mut frontier = MinFrontier::<LabSpace, _>::new(rng, 4, 4, seeds, Connectivity::Eight);
+
+        let first = frontier.place_debug(Rgb8::from([255, 0, 0])).unwrap();
+        assert_eq!(first.pos, (0, 0));
+        assert!(first.nearest.is_none());
+        assert_eq!(first.neighbors.len(), 3);
+        assert!(first.neighbors.iter().all(|(_, s)| *s == PixelState::Frontier));
+        assert!(first.to_string().contains("placed at (0, 0)"));
+
+        let second = frontier.place_debug(Rgb8::from([255, 0, 0])).unwrap();
+        let nearest = second.nearest.as_ref().unwrap();
+        assert_eq!(nearest.pos, (0, 0));
+        assert_eq!(nearest.distance, 0.0);
+        assert_eq!(frontier.pixel_state(0, 0), PixelState::Filled);
+        assert_eq!(frontier.pixel_state(3, 3), PixelState::Empty);
+    }
+
+    /// Check that visited_pixels() yields exactly the successful placements.
+    fn check_visited_pixels(mut frontier: impl Frontier) {
+        let mut placed = Vec::new();
+        for i in 0..32 {
+            let rgb8 = Rgb8::from([8 * i, 255 - 8 * i, 0]);
+            if let Some((x, y)) = frontier.place(rgb8) {
+                placed.push((x, y, rgb8));
+            }
+        }
+
+        let mut visited: Vec<_> = frontier.visited_pixels().collect();
+        assert_eq!(visited.len(), placed.len());
+        assert!(visited
+            .iter()
+            .all(|&(x, y, _)| x < frontier.width() && y < frontier.height()));
+
+        placed.sort_by_key(|&(x, y, _)| (y, x));
+        visited.sort_by_key(|&(x, y, _)| (y, x));
+        assert_eq!(visited, placed);
+    }
+
+    #[test]
+    fn test_visited_pixels() {
+        let rng = Pcg64::seed_from_u64(0);
+        let seeds = vec![(2, 1)];
+        let eight = Connectivity::Eight;
+        check_visited_pixels(MinFrontier::<LabSpace, _>::new(rng, 5, 4, seeds.clone(), eight));
+        check_visited_pixels(MeanFrontier::<LabSpace>::new(5, 4, seeds, eight));
+    }
+
+    /// Paint some colors, and return where they were placed.
+    fn paint(frontier: &mut impl Frontier, n: u8) -> Vec<(u32, u32)> {
+        (0..n)
+            .map(|i| Rgb8::from([2 * i, 255 - 2 * i, i]))
+            .map_while(|rgb8| frontier.place(rgb8))
+            .collect()
+    }
+
+    #[test]
+    fn test_multiple_seeds() {
+        let seeds = vec![(0, 0), (63, 63), (0, 0)];
+
+        let rng = Pcg64::seed_from_u64(0);
+        let eight = Connectivity::Eight;
+        let mut frontier = MinFrontier::<LabSpace, _>::new(rng, 64, 64, seeds.clone(), eight);
+        let seeded = paint(&mut frontier, 100);
+        assert_eq!(&seeded[..2], &[(0, 0), (63, 63)]);
+        assert!(seeded.iter().any(|&(x, y)| x > 32 && y > 32));
+
+        let rng = Pcg64::seed_from_u64(0);
+        let mut frontier = MinFrontier::<LabSpace, _>::new(rng, 64, 64, vec![(32, 32)], eight);
+        let centered = paint(&mut frontier, 100);
+        assert_ne!(seeded, centered);
+
+        let mut frontier = MeanFrontier::<LabSpace>::new(64, 64, seeds, eight);
+        assert_eq!(frontier.len(), 2);
+        assert_eq!(frontier.pixel_state(0, 0), PixelState::Frontier);
+        assert_eq!(frontier.pixel_state(63, 63), PixelState::Frontier);
+        assert_eq!(paint(&mut frontier, 100).len(), 100);
+    }
+
+    /// Check that a frontier's statistics are consistent.
+    fn check_statistics(mut frontier: impl Frontier, n: usize) {
+        assert_eq!(frontier.statistics(), Statistics::default());
+
+        let placed = paint(&mut frontier, n as u8).len();
+        assert_eq!(placed, n);
+
+        let stats = frontier.statistics();
+        assert_eq!(stats.pixels_placed, n);
+        assert!(stats.max_frontier_size > 0);
+        assert!(stats.min_distance >= 0.0);
+        assert!(stats.min_distance <= stats.max_distance);
+        assert!(stats.total_distance_sum <= stats.max_distance * stats.pixels_placed as f64);
+        assert!(stats.to_string().contains(&format!("Placed {} pixels", n)));
+    }
+
+    #[test]
+    fn test_statistics() {
+        let rng = Pcg64::seed_from_u64(0);
+        let seeds = vec![(4, 4)];
+        let eight = Connectivity::Eight;
+        check_statistics(MinFrontier::<LabSpace, _>::new(rng, 9, 9, seeds.clone(), eight), 81);
+        check_statistics(MeanFrontier::<LabSpace>::new(9, 9, seeds, eight), 81);
+
+        let img = RgbaImage::from_fn(9, 9, |x, y| Rgba([28 * x as u8, 28 * y as u8, 0, 255]));
+        check_statistics(ImageFrontier::<LabSpace>::new(&img), 81);
+    }
+
+    #[test]
+    fn test_merge_statistics() {
+        let mut a = Statistics::default();
+        a.record(None, 4);
+        a.record(Some(2.0), 3);
+
+        let mut b = Statistics::default();
+        b.record(Some(1.0), 8);
+        b.record(Some(6.0), 2);
+
+        let mut merged = Statistics::default();
+        merged.merge(&a);
+        merged.merge(&b);
+        assert_eq!(merged.pixels_placed, 4);
+        assert_eq!(merged.max_frontier_size, 8);
+        assert_eq!(merged.min_distance, 1.0);
+        assert_eq!(merged.max_distance, 6.0);
+        assert_eq!(merged.mean_distance(), 3.0);
+    }
+
+    /// Check that every placement is next to an already filled pixel.
+    fn check_connected(frontier: &mut impl Frontier, connectivity: Connectivity) {
+        let mut filled = HashSet::new();
+        for (i, pos) in paint(frontier, 81).into_iter().enumerate() {
+            let (x, y) = pos;
+            if i > 0 {
+                let neighbors = connectivity.neighbors(x, y);
+                assert!(neighbors.iter().any(|n| filled.contains(n)), "{:?}", pos);
+            }
+            filled.insert(pos);
+        }
+        assert_eq!(filled.len(), 81);
+    }
+
+    #[test]
+    fn test_four_connectivity() {
+        let four = Connectivity::Four;
+        assert_eq!(*four.neighbors(5, 5), [(4, 5), (5, 4), (5, 6), (6, 5)]);
+        assert_eq!(Connectivity::Eight.neighbors(5, 5).len(), 8);
+
+        let rng = Pcg64::seed_from_u64(0);
+        check_connected(&mut MinFrontier::<LabSpace, _>::new(rng, 9, 9, vec![(4, 4)], four), four);
+        check_connected(&mut MeanFrontier::<LabSpace>::new(9, 9, vec![(4, 4)], four), four);
+
+        // With 8-connectivity, some pixels are only reached diagonally
+        let rng = Pcg64::seed_from_u64(0);
+        let eight = Connectivity::Eight;
+        let mut frontier = MinFrontier::<LabSpace, _>::new(rng, 9, 9, vec![(4, 4)], eight);
+        let placed = paint(&mut frontier, 81);
+        assert!(placed.iter().enumerate().skip(1).any(|(i, &(x, y))| {
+            !four.neighbors(x, y).iter().any(|n| placed[..i].contains(n))
+        }));
+    }
+}
@@ -0,0 +1,228 @@
+//! Fountain frontier.
+
+use super::{Connectivity, Frontier, Pixel, PixelState, Placement, Statistics, Target};
+
+use crate::color::{ColorSpace, Rgb8};
+use crate::forest::KdForest;
+
+use acap::knn::NearestNeighbors;
+
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+
+/// A [Frontier] that grows in rings around the seeds, regardless of the order of the colors.
+///
+/// Pixels are filled in order of their Manhattan distance from the closest seed.  Each ring is
+/// only admitted to the frontier once the previous one is complete, and colors are placed on the
+/// pixel of the current ring whose neighbors have the closest average color.
+#[derive(Debug)]
+pub struct FountainFrontier<C> {
+    filled: Vec<Option<(C, Rgb8)>>,
+    rings: Vec<u32>,
+    /// The pixels that haven't been admitted yet, keyed by (ring, y, x).
+    queue: BinaryHeap<Reverse<(u32, u32, u32)>>,
+    /// The unfilled pixels of the current ring.
+    forest: KdForest<Pixel<C>>,
+    ring: u32,
+    width: u32,
+    height: u32,
+    connectivity: Connectivity,
+    len: usize,
+    stats: Statistics,
+}
+
+impl<C: ColorSpace> FountainFrontier<C>
+where
+    C::Value: PartialOrd<C::Distance>,
+{
+    /// Create a FountainFrontier with the given dimensions and initial pixel locations.
+    pub fn new(
+        width: u32,
+        height: u32,
+        seeds: Vec<(u32, u32)>,
+        connectivity: Connectivity,
+    ) -> Self {
+        let size = (width as usize) * (height as usize);
+        let mut rings = Vec::with_capacity(size);
+        let mut queue = BinaryHeap::with_capacity(size);
+        for y in 0..height {
+            for x in 0..width {
+                let ring = seeds
+                    .iter()
+                    .map(|&(x0, y0)| x.abs_diff(x0) + y.abs_diff(y0))
+                    .min()
+                    .unwrap_or(u32::MAX);
+                rings.push(ring);
+                queue.push(Reverse((ring, y, x)));
+            }
+        }
+
+        let mut frontier = Self {
+            filled: vec![None; size],
+            rings,
+            queue,
+            forest: KdForest::new(),
+            ring: 0,
+            width,
+            height,
+            connectivity,
+            len: 0,
+            stats: Statistics::default(),
+        };
+        if !seeds.is_empty() {
+            frontier.next_ring();
+        }
+        frontier
+    }
+
+    fn pixel_index(&self, x: u32, y: u32) -> usize {
+        debug_assert!(x < self.width);
+        debug_assert!(y < self.height);
+
+        (x + y * self.width) as usize
+    }
+
+    /// The average color of a pixel's filled neighbors.
+    fn center(&self, x: u32, y: u32) -> C {
+        let colors: Vec<_> = self
+            .connectivity
+            .neighbors(x, y)
+            .iter()
+            .filter(|(x, y)| *x < self.width && *y < self.height)
+            .filter_map(|&(x, y)| self.filled[self.pixel_index(x, y)])
+            .map(|(color, _)| color)
+            .collect();
+
+        if colors.is_empty() {
+            C::from(Rgb8::from([0, 0, 0]))
+        } else {
+            C::average(colors)
+        }
+    }
+
+    /// Admit the next ring of pixels to the frontier.
+    fn next_ring(&mut self) {
+        let ring = match self.queue.peek() {
+            Some(Reverse((ring, _, _))) if *ring < u32::MAX => *ring,
+            _ => return,
+        };
+
+        let mut pixels = Vec::new();
+        while let Some(&Reverse((r, y, x))) = self.queue.peek() {
+            if r != ring {
+                break;
+            }
+            self.queue.pop();
+            pixels.push(Pixel::new(x, y, self.center(x, y)));
+        }
+
+        self.ring = ring;
+        self.len = pixels.len();
+        self.forest = pixels.into_iter().collect();
+    }
+
+    /// Fill the closest pixel of the current ring to a color, returning its position, color, and
+    /// distance.
+    fn fill_nearest(&mut self, rgb8: Rgb8) -> Option<((u32, u32), C, C::Distance)> {
+        let color = C::from(rgb8);
+
+        let nearest = self.forest.nearest(&Target(color))?;
+        let (pos, target, distance) = (nearest.item.pos, nearest.item.color, nearest.distance);
+        nearest.item.delete();
+
+        let i = self.pixel_index(pos.0, pos.1);
+        self.filled[i] = Some((color, rgb8));
+
+        self.len -= 1;
+        if self.len == 0 {
+            self.next_ring();
+        }
+        self.stats.record(Some(distance.into()), self.len);
+
+        Some((pos, target, distance))
+    }
+}
+
+impl<C: ColorSpace> Frontier for FountainFrontier<C>
+where
+    C::Value: PartialOrd<C::Distance>,
+{
+    fn width(&self) -> u32 {
+        self.width
+    }
+
+    fn height(&self) -> u32 {
+        self.height
+    }
+
+    fn len(&self) -> usize {
+        self.len
+    }
+
+    fn place(&mut self, rgb8: Rgb8) -> Option<(u32, u32)> {
+        self.fill_nearest(rgb8).map(|n| n.0)
+    }
+
+    fn place_debug(&mut self, rgb8: Rgb8) -> Option<Placement> {
+        let nearest = self.fill_nearest(rgb8)?;
+        Some(Placement::new(self, rgb8, nearest.0, Some(nearest)))
+    }
+
+    fn pixel_state(&self, x: u32, y: u32) -> PixelState {
+        let i = self.pixel_index(x, y);
+        if self.filled[i].is_some() {
+            PixelState::Filled
+        } else if self.rings[i] == self.ring && self.len > 0 {
+            PixelState::Frontier
+        } else {
+            PixelState::Empty
+        }
+    }
+
+    fn visited_pixels(&self) -> impl Iterator<Item = (u32, u32, Rgb8)> {
+        let width = self.width;
+        self.filled.iter().enumerate().filter_map(move |(i, pixel)| {
+            let i = i as u32;
+            pixel.map(|(_, rgb8)| (i % width, i / width, rgb8))
+        })
+    }
+
+    fn statistics(&self) -> Statistics {
+        self.stats
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::color::LabSpace;
+
+    #[test]
+    fn test_fountain() {
+        let eight = Connectivity::Eight;
+        let mut frontier = FountainFrontier::<LabSpace>::new(10, 10, vec![(5, 5)], eight);
+        assert_eq!(frontier.len(), 1);
+        assert_eq!(frontier.pixel_state(5, 5), PixelState::Frontier);
+
+        let mut placed: Vec<_> = (0..5)
+            .map(|i| frontier.place(Rgb8::from([50 * i, 255 - 50 * i, 0])).unwrap())
+            .collect();
+        assert_eq!(placed[0], (5, 5));
+        placed.sort_unstable();
+        assert_eq!(placed, [(4, 5), (5, 4), (5, 5), (5, 6), (6, 5)]);
+
+        // The next ring is the diamond at distance 2
+        assert_eq!(frontier.len(), 8);
+        assert_eq!(frontier.pixel_state(7, 5), PixelState::Frontier);
+        assert_eq!(frontier.pixel_state(6, 6), PixelState::Frontier);
+        assert_eq!(frontier.pixel_state(7, 6), PixelState::Empty);
+
+        for i in 5..100 {
+            assert!(frontier.place(Rgb8::from([i, i, i])).is_some());
+        }
+        assert!(frontier.is_empty());
+        assert_eq!(frontier.place(Rgb8::from([0, 0, 0])), None);
+        assert_eq!(frontier.visited_pixels().count(), 100);
+    }
+}
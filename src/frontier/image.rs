@@ -1,22 +1,42 @@
 //! Frontier that targets an image.
 
-use super::{Frontier, Pixel, Target};
+use super::{Frontier, Pixel, PixelState, Placement, Statistics, Target};
 
 use crate::color::{ColorSpace, Rgb8};
 use crate::forest::KdForest;
 
 use acap::knn::NearestNeighbors;
 
-use image::RgbImage;
+use image::{Rgba, RgbaImage};
+
+/// Target pixels more transparent than this are left out of the frontier.
+const MIN_ALPHA: u8 = 128;
+
+/// The default fraction of deleted pixels that triggers a rebuild.
+pub const REBUILD_RATIO: f64 = 1.0 / 32.0;
+
+/// Premultiply a color by its alpha, i.e. blend it over black.
+fn premultiply(rgba: Rgba<u8>) -> Rgb8 {
+    let alpha = u16::from(rgba[3]);
+    Rgb8::from([0, 1, 2].map(|i| (u16::from(rgba[i]) * alpha / 255) as u8))
+}
 
 /// A [Frontier] that places colors on the closest pixel of a target image.
 #[derive(Debug)]
 pub struct ImageFrontier<C> {
     nodes: KdForest<Pixel<C>>,
+    filled: Vec<Option<Rgb8>>,
+    alpha: Vec<u8>,
+    mask: Vec<u8>,
+    softness: u8,
+    rebuild_ratio: Option<f64>,
     width: u32,
     height: u32,
     len: usize,
     deleted: usize,
+    placed: usize,
+    rejections: Option<usize>,
+    stats: Statistics,
 }
 
 impl<C: ColorSpace> ImageFrontier<C>
@@ -24,21 +44,117 @@ where
     C::Value: PartialOrd<C::Distance>,
 {
     /// Create an ImageFrontier from an image.
-    pub fn new(img: &RgbImage) -> Self {
+    ///
+    /// Only the opaque parts of the image are filled: mostly transparent pixels are left out, and
+    /// the colors of partially transparent ones are premultiplied by their alpha.  The placed
+    /// colors keep the image's alpha channel.
+    pub fn new(img: &RgbaImage) -> Self {
         let width = img.width();
         let height = img.height();
-        let len = (width as usize) * (height as usize);
+        let size = (width as usize) * (height as usize);
+
+        let nodes: Vec<_> = img
+            .enumerate_pixels()
+            .filter(|(_, _, p)| p[3] >= MIN_ALPHA)
+            .map(|(x, y, p)| Pixel::new(x, y, C::from(premultiply(*p))))
+            .collect();
 
         Self {
-            nodes: img
-                .enumerate_pixels()
-                .map(|(x, y, p)| Pixel::new(x, y, C::from(*p)))
-                .collect(),
+            len: nodes.len(),
+            nodes: nodes.into_iter().collect(),
+            filled: vec![None; size],
+            alpha: vec![0; size],
+            mask: img.pixels().map(|p| p[3]).collect(),
+            softness: 0,
+            rebuild_ratio: Some(REBUILD_RATIO),
             width,
             height,
-            len,
             deleted: 0,
+            placed: 0,
+            rejections: None,
+            stats: Statistics::default(),
+        }
+    }
+
+    /// Start counting rejections, i.e. already-filled pixels that were closer than the pixel that
+    /// was eventually chosen.  This slows down placement somewhat.
+    pub fn track_rejections(&mut self) {
+        self.rejections.get_or_insert(0);
+    }
+
+    /// The average number of rejections per placement, if they are being tracked.
+    pub fn rejection_rate(&self) -> Option<f64> {
+        self.rejections
+            .map(|r| if self.placed == 0 { 0.0 } else { r as f64 / self.placed as f64 })
+    }
+
+    /// Set the softness of the target's edges, as a percentage.
+    ///
+    /// With a nonzero softness, a color that is far from its target pixel only partially covers
+    /// it.  The pixel remains on the frontier until enough colors have been placed on it to make
+    /// it fully opaque.
+    pub fn set_softness(&mut self, softness: u8) {
+        self.softness = softness.min(100);
+    }
+
+    /// Set the fraction of the frontier that must be deleted before it is rebuilt, or `None` to
+    /// never rebuild it.
+    ///
+    /// Rebuilding is expensive, but searching through lots of deleted pixels is too.
+    pub fn set_rebuild_ratio(&mut self, ratio: Option<f64>) {
+        self.rebuild_ratio = ratio;
+    }
+
+    /// The opacity of a color placed at the given distance from its target.
+    fn coverage(&self, distance: f64) -> u8 {
+        if self.softness == 0 {
+            return 255;
+        }
+
+        // Scale distances by the distance from black to white
+        let black = C::from(Rgb8::from([0, 0, 0]));
+        let white = C::from(Rgb8::from([255, 255, 255]));
+        let scale: f64 = black.distance(&white).into();
+
+        let softness = f64::from(self.softness) / 100.0;
+        let opacity = 1.0 - softness * (distance / scale).min(1.0);
+        (255.0 * opacity).round().max(1.0) as u8
+    }
+
+    /// Fill the closest pixel to a color, returning its position, color, and distance.
+    fn fill_nearest(&mut self, rgb8: Rgb8) -> Option<((u32, u32), C, C::Distance)> {
+        let color = C::from(rgb8);
+        let hits = self.rejections.map(|_| self.nodes.deleted_hits());
+
+        let nearest = self.nodes.nearest(&Target(color))?;
+        let (pos, target, distance) = (nearest.item.pos, nearest.item.color, nearest.distance);
+
+        let (x, y) = pos;
+        let i = (x + y * self.width) as usize;
+        self.filled[i] = Some(rgb8);
+        self.alpha[i] = self.alpha[i].saturating_add(self.coverage(distance.into()));
+        if self.alpha[i] == 255 {
+            nearest.item.delete();
+            self.deleted += 1;
+        }
+
+        if let (Some(rejections), Some(hits)) = (&mut self.rejections, hits) {
+            *rejections += self.nodes.deleted_hits() - hits;
         }
+        self.placed += 1;
+
+        if let Some(ratio) = self.rebuild_ratio {
+            // A plain `>=` would pick up the C::Value: PartialOrd<C::Distance> bound
+            let threshold = ratio * self.len as f64;
+            if (self.deleted as f64).total_cmp(&threshold).is_ge() {
+                self.nodes.rebuild();
+                self.len -= self.deleted;
+                self.deleted = 0;
+            }
+        }
+        self.stats.record(Some(distance.into()), self.len - self.deleted);
+
+        Some((pos, target, distance))
     }
 }
 
@@ -59,23 +175,168 @@ where
     }
 
     fn place(&mut self, rgb8: Rgb8) -> Option<(u32, u32)> {
-        let color = C::from(rgb8);
+        self.fill_nearest(rgb8).map(|n| n.0)
+    }
 
-        if let Some(node) = self.nodes.nearest(&Target(color)).map(|n| n.item) {
-            let pos = node.pos;
+    fn place_debug(&mut self, rgb8: Rgb8) -> Option<Placement> {
+        let nearest = self.fill_nearest(rgb8)?;
+        Some(Placement::new(self, rgb8, nearest.0, Some(nearest)))
+    }
 
-            node.delete();
-            self.deleted += 1;
+    fn pixel_state(&self, x: u32, y: u32) -> PixelState {
+        let i = (x + y * self.width) as usize;
+        if self.mask[i] < MIN_ALPHA {
+            PixelState::Empty
+        } else if self.alpha[i] == 255 {
+            PixelState::Filled
+        } else {
+            PixelState::Frontier
+        }
+    }
 
-            if 32 * self.deleted >= self.len {
-                self.nodes.rebuild();
-                self.len -= self.deleted;
-                self.deleted = 0;
+    fn pixel_alpha(&self, x: u32, y: u32) -> u8 {
+        let i = (x + y * self.width) as usize;
+        (u16::from(self.alpha[i]) * u16::from(self.mask[i]) / 255) as u8
+    }
+
+    fn visited_pixels(&self) -> impl Iterator<Item = (u32, u32, Rgb8)> {
+        let width = self.width;
+        self.filled.iter().enumerate().filter_map(move |(i, rgb8)| {
+            let i = i as u32;
+            rgb8.map(|rgb8| (i % width, i / width, rgb8))
+        })
+    }
+
+    fn statistics(&self) -> Statistics {
+        self.stats
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::color::source::AllColors;
+    use crate::color::{order, LabSpace};
+
+    use rand::prelude::*;
+    use rand_pcg::Pcg64;
+
+    /// Make an opaque pixel.
+    fn opaque([r, g, b]: [u8; 3]) -> Rgba<u8> {
+        Rgba([r, g, b, 255])
+    }
+
+    /// Compute the rejection rate of painting a target image with hue-sorted colors.
+    fn rejection_rate(img: &RgbaImage) -> f64 {
+        let mut frontier = ImageFrontier::<LabSpace>::new(img);
+        frontier.track_rejections();
+
+        for color in order::hue_sorted(AllColors::new(3, 4, 3)) {
+            frontier.place(color);
+        }
+
+        frontier.rejection_rate().unwrap()
+    }
+
+    #[test]
+    fn test_rejection_rate() {
+        let mut rng = Pcg64::seed_from_u64(0);
+        let random = RgbaImage::from_fn(32, 32, |_, _| opaque(rng.gen()));
+        let uniform = RgbaImage::from_pixel(32, 32, opaque([128, 128, 128]));
+
+        let untracked = ImageFrontier::<LabSpace>::new(&random);
+        assert_eq!(untracked.rejection_rate(), None);
+
+        assert!(rejection_rate(&random) < rejection_rate(&uniform));
+    }
+
+    /// Paint a target image with hue-sorted colors, returning the placements and final alphas.
+    fn paint_soft(img: &RgbaImage, softness: u8) -> (Vec<Option<(u32, u32)>>, Vec<u8>) {
+        let mut frontier = ImageFrontier::<LabSpace>::new(img);
+        frontier.set_softness(softness);
+
+        let placements = order::hue_sorted(AllColors::new(3, 3, 2))
+            .into_iter()
+            .map(|color| frontier.place(color))
+            .collect();
+
+        (placements, frontier.alpha)
+    }
+
+    #[test]
+    fn test_softness() {
+        let mut rng = Pcg64::seed_from_u64(0);
+        let img = RgbaImage::from_fn(16, 16, |_, _| opaque(rng.gen()));
+
+        let (placements, alpha) = paint_soft(&img, 100);
+        assert!(alpha.iter().any(|&a| a > 0 && a < 255));
+
+        let mut frontier = ImageFrontier::<LabSpace>::new(&img);
+        let hard: Vec<_> = order::hue_sorted(AllColors::new(3, 3, 2))
+            .into_iter()
+            .map(|color| frontier.place(color))
+            .collect();
+        assert_ne!(placements, hard);
+        assert_eq!(paint_soft(&img, 0).0, hard);
+    }
+
+    #[test]
+    fn test_rebuild_ratio() {
+        let mut rng = Pcg64::seed_from_u64(0);
+        let img = RgbaImage::from_fn(16, 16, |_, _| opaque(rng.gen()));
+
+        for ratio in [None, Some(1.0 / 64.0), Some(1.0 / 8.0), Some(1.0)] {
+            let mut frontier = ImageFrontier::<LabSpace>::new(&img);
+            frontier.set_rebuild_ratio(ratio);
+
+            for (i, color) in order::hue_sorted(AllColors::new(3, 3, 2)).into_iter().enumerate() {
+                assert!(frontier.place(color).is_some());
+                assert_eq!(frontier.len(), 255 - i);
             }
+            assert!(frontier.is_empty());
+        }
+    }
 
-            Some(pos)
-        } else {
-            None
+    #[test]
+    fn test_alpha_mask() {
+        // An opaque circle, with a translucent ring around it
+        let img = RgbaImage::from_fn(16, 16, |x, y| {
+            let r2 = (x as i32 - 8).pow(2) + (y as i32 - 8).pow(2);
+            let alpha = match r2 {
+                0..=25 => 255,
+                26..=36 => 160,
+                _ => 0,
+            };
+            Rgba([255, 255, 255, alpha])
+        });
+        let inside = |x: u32, y: u32| img.get_pixel(x, y)[3] >= MIN_ALPHA;
+        let n = img.pixels().filter(|p| p[3] >= MIN_ALPHA).count();
+
+        let mut frontier = ImageFrontier::<LabSpace>::new(&img);
+        assert_eq!(frontier.len(), n);
+
+        let placed: Vec<_> = order::hue_sorted(AllColors::new(3, 3, 3))
+            .into_iter()
+            .map_while(|color| frontier.place(color))
+            .collect();
+        assert_eq!(placed.len(), n);
+        assert!(frontier.is_empty());
+
+        for y in 0..16 {
+            for x in 0..16 {
+                let alpha = img.get_pixel(x, y)[3];
+                if inside(x, y) {
+                    assert!(placed.contains(&(x, y)));
+                    assert_eq!(frontier.pixel_state(x, y), PixelState::Filled);
+                    assert_eq!(frontier.pixel_alpha(x, y), alpha);
+                } else {
+                    assert_eq!(frontier.pixel_state(x, y), PixelState::Empty);
+                    assert_eq!(frontier.pixel_alpha(x, y), 0);
+                }
+            }
         }
+
+        assert_eq!(premultiply(Rgba([255, 100, 0, 160])), Rgb8::from([160, 62, 0]));
     }
 }
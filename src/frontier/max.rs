@@ -0,0 +1,105 @@
+//! Maximum selection frontier.
+
+use super::min::MinFrontier;
+use super::{Connectivity, Frontier, PixelState, Placement};
+
+use crate::color::{ColorSpace, Rgb8};
+
+use rand::Rng;
+
+/// A [Frontier] that places colors on a neighbor of the furthest pixel so far.
+///
+/// This is the opposite of a [MinFrontier], and places each color next to the pixel it contrasts
+/// with the most.  Finding that pixel takes a linear scan, so this frontier is much slower.
+#[derive(Debug)]
+pub struct MaxFrontier<C, R> {
+    inner: MinFrontier<C, R>,
+}
+
+impl<C: ColorSpace, R: Rng> MaxFrontier<C, R>
+where
+    C::Value: PartialOrd<C::Distance>,
+{
+    /// Create a MaxFrontier with the given dimensions and initial pixel locations.
+    pub fn new(
+        rng: R,
+        width: u32,
+        height: u32,
+        seeds: Vec<(u32, u32)>,
+        connectivity: Connectivity,
+    ) -> Self {
+        Self {
+            inner: MinFrontier::new(rng, width, height, seeds, connectivity),
+        }
+    }
+}
+
+impl<C: ColorSpace, R: Rng> Frontier for MaxFrontier<C, R>
+where
+    C::Value: PartialOrd<C::Distance>,
+{
+    fn width(&self) -> u32 {
+        self.inner.width()
+    }
+
+    fn height(&self) -> u32 {
+        self.inner.height()
+    }
+
+    fn len(&self) -> usize {
+        self.inner.len()
+    }
+
+    fn place(&mut self, rgb8: Rgb8) -> Option<(u32, u32)> {
+        let color = C::from(rgb8);
+        let far = self.inner.furthest(color).map(|n| n.0);
+        self.inner.place_near(rgb8, color, far)
+    }
+
+    fn place_debug(&mut self, rgb8: Rgb8) -> Option<Placement> {
+        let color = C::from(rgb8);
+        let furthest = self.inner.furthest(color);
+        let pos = self.inner.place_near(rgb8, color, furthest.map(|n| n.0))?;
+        Some(Placement::new(self, rgb8, pos, furthest))
+    }
+
+    fn pixel_state(&self, x: u32, y: u32) -> PixelState {
+        self.inner.pixel_state(x, y)
+    }
+
+    fn visited_pixels(&self) -> impl Iterator<Item = (u32, u32, Rgb8)> {
+        self.inner.visited_pixels()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::color::LabSpace;
+
+    use rand::SeedableRng;
+    use rand_pcg::Pcg64;
+
+    #[test]
+    fn test_furthest() {
+        let rng = Pcg64::seed_from_u64(0);
+        let seeds = vec![(8, 8)];
+        let mut frontier = MaxFrontier::<LabSpace, _>::new(rng, 16, 16, seeds, Connectivity::Eight);
+
+        let black = frontier.place(Rgb8::from([0, 0, 0])).unwrap();
+        let white = frontier.place(Rgb8::from([255, 255, 255])).unwrap();
+        assert_eq!(black, (8, 8));
+
+        // A dark color goes next to the white pixel, not the black one
+        let dark = frontier.place_debug(Rgb8::from([16, 16, 16])).unwrap();
+        assert_eq!(dark.nearest.unwrap().pos, white);
+
+        for i in 0..253 {
+            assert!(frontier.place(Rgb8::from([i, 255 - i, i / 2])).is_some());
+        }
+        assert_eq!(frontier.visited_pixels().count(), 256);
+        assert!(frontier.is_empty());
+        assert_eq!(frontier.place(Rgb8::from([0, 0, 0])), None);
+    }
+}
@@ -1,20 +1,18 @@
 //! Mean selection frontier.
 
-use super::{neighbors, Frontier, RcPixel, Target};
+use super::{Connectivity, Frontier, PixelState, Placement, RcPixel, Statistics, Target};
 
 use crate::color::{ColorSpace, Rgb8};
 use crate::forest::KdForest;
 
 use acap::knn::NearestNeighbors;
 
-use std::iter;
-
 /// A pixel on a mean frontier.
 #[derive(Debug)]
 enum MeanPixel<C> {
     Empty,
     Fillable(RcPixel<C>),
-    Filled(C),
+    Filled(C, Rgb8),
 }
 
 impl<C: ColorSpace> MeanPixel<C>
@@ -23,7 +21,7 @@ where
 {
     fn filled_color(&self) -> Option<C> {
         match self {
-            Self::Filled(color) => Some(*color),
+            Self::Filled(color, _) => Some(*color),
             _ => None,
         }
     }
@@ -36,33 +34,63 @@ pub struct MeanFrontier<C> {
     forest: KdForest<RcPixel<C>>,
     width: u32,
     height: u32,
+    connectivity: Connectivity,
     len: usize,
     deleted: usize,
+    stats: Statistics,
+    /// Combines the colors of a pixel's filled neighbors into its target color.
+    center: fn(Vec<C>) -> C,
 }
 
 impl<C: ColorSpace> MeanFrontier<C>
 where
     C::Value: PartialOrd<C::Distance>,
 {
-    /// Create a MeanFrontier with the given dimensions and initial pixel location.
-    pub fn new(width: u32, height: u32, x0: u32, y0: u32) -> Self {
+    /// Create a MeanFrontier with the given dimensions and initial pixel locations.
+    pub fn new(
+        width: u32,
+        height: u32,
+        seeds: Vec<(u32, u32)>,
+        connectivity: Connectivity,
+    ) -> Self {
+        Self::with_center(width, height, seeds, connectivity, C::average::<Vec<C>>)
+    }
+
+    /// Create a MeanFrontier that combines neighboring colors with something other than their
+    /// average.
+    pub(super) fn with_center(
+        width: u32,
+        height: u32,
+        seeds: Vec<(u32, u32)>,
+        connectivity: Connectivity,
+        center: fn(Vec<C>) -> C,
+    ) -> Self {
         let size = (width as usize) * (height as usize);
         let mut pixels = Vec::with_capacity(size);
         for _ in 0..size {
             pixels.push(MeanPixel::Empty);
         }
 
-        let pixel0 = RcPixel::new(x0, y0, C::from(Rgb8::from([0, 0, 0])));
-        let i = (x0 + y0 * width) as usize;
-        pixels[i] = MeanPixel::Fillable(pixel0.clone());
+        let mut forest = Vec::new();
+        for (x0, y0) in seeds {
+            let i = (x0 + y0 * width) as usize;
+            if let MeanPixel::Empty = pixels[i] {
+                let pixel0 = RcPixel::new(x0, y0, C::from(Rgb8::from([0, 0, 0])));
+                pixels[i] = MeanPixel::Fillable(pixel0.clone());
+                forest.push(pixel0);
+            }
+        }
 
         Self {
             pixels,
-            forest: iter::once(pixel0).collect(),
+            len: forest.len(),
+            forest: forest.into_iter().collect(),
             width,
             height,
-            len: 1,
+            connectivity,
             deleted: 0,
+            stats: Statistics::default(),
+            center,
         }
     }
 
@@ -73,7 +101,7 @@ where
         (x + y * self.width) as usize
     }
 
-    fn fill(&mut self, x: u32, y: u32, color: C) {
+    fn fill(&mut self, x: u32, y: u32, rgb8: Rgb8, color: C) {
         let i = self.pixel_index(x, y);
         match &self.pixels[i] {
             MeanPixel::Empty => {}
@@ -83,10 +111,10 @@ where
             }
             _ => unreachable!(),
         }
-        self.pixels[i] = MeanPixel::Filled(color);
+        self.pixels[i] = MeanPixel::Filled(color, rgb8);
 
         let mut pixels = Vec::new();
-        for &(x, y) in &neighbors(x, y) {
+        for &(x, y) in self.connectivity.neighbors(x, y).iter() {
             if x < self.width && y < self.height {
                 let i = self.pixel_index(x, y);
                 match &self.pixels[i] {
@@ -95,16 +123,17 @@ where
                         pixel.delete();
                         self.deleted += 1;
                     }
-                    MeanPixel::Filled(_) => continue,
+                    MeanPixel::Filled(..) => continue,
                 }
-                let color = C::average(
-                    neighbors(x, y)
+                let color = (self.center)(
+                    self.connectivity
+                        .neighbors(x, y)
                         .iter()
                         .filter(|(x, y)| *x < self.width && *y < self.height)
                         .map(|(x, y)| self.pixel_index(*x, *y))
                         .map(|i| &self.pixels[i])
-                        .map(MeanPixel::filled_color)
-                        .flatten(),
+                        .filter_map(MeanPixel::filled_color)
+                        .collect(),
                 );
                 let pixel = RcPixel::new(x, y, color);
                 self.pixels[i] = MeanPixel::Fillable(pixel.clone());
@@ -135,10 +164,54 @@ where
 
     fn place(&mut self, rgb8: Rgb8) -> Option<(u32, u32)> {
         let color = C::from(rgb8);
-        let (x, y) = self.forest.nearest(&Target(color)).map(|n| n.item.pos)?;
+        let (x, y, distance) = self
+            .forest
+            .nearest(&Target(color))
+            .map(|n| (n.item.pos.0, n.item.pos.1, n.distance))?;
 
-        self.fill(x, y, color);
+        self.fill(x, y, rgb8, color);
+        self.stats.record(Some(distance.into()), self.len());
 
         Some((x, y))
     }
+
+    fn place_debug(&mut self, rgb8: Rgb8) -> Option<Placement> {
+        let color = C::from(rgb8);
+        let nearest = self
+            .forest
+            .nearest(&Target(color))
+            .map(|n| (n.item.pos, n.item.color, n.distance))?;
+
+        let (x, y) = nearest.0;
+        self.fill(x, y, rgb8, color);
+        self.stats.record(Some(nearest.2.into()), self.len());
+
+        Some(Placement::new(self, rgb8, (x, y), Some(nearest)))
+    }
+
+    fn pixel_state(&self, x: u32, y: u32) -> PixelState {
+        match self.pixels[self.pixel_index(x, y)] {
+            MeanPixel::Empty => PixelState::Empty,
+            MeanPixel::Fillable(_) => PixelState::Frontier,
+            MeanPixel::Filled(..) => PixelState::Filled,
+        }
+    }
+
+    fn visited_pixels(&self) -> impl Iterator<Item = (u32, u32, Rgb8)> {
+        let width = self.width;
+        self.pixels
+            .iter()
+            .enumerate()
+            .filter_map(move |(i, pixel)| match pixel {
+                MeanPixel::Filled(_, rgb8) => {
+                    let i = i as u32;
+                    Some((i % width, i / width, *rgb8))
+                }
+                _ => None,
+            })
+    }
+
+    fn statistics(&self) -> Statistics {
+        self.stats
+    }
 }
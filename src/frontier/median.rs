@@ -0,0 +1,106 @@
+//! Median selection frontier.
+
+use super::mean::MeanFrontier;
+use super::{Connectivity, Frontier, PixelState, Placement, Statistics};
+
+use crate::color::{ColorSpace, Rgb8};
+
+/// A [Frontier] that looks at the geometric median color of each pixel's neighbors.
+///
+/// This works like a [MeanFrontier], but a single neighbor with an outlying color has less
+/// influence on where similar colors get placed.
+#[derive(Debug)]
+pub struct MedianFrontier<C> {
+    inner: MeanFrontier<C>,
+}
+
+impl<C: ColorSpace> MedianFrontier<C>
+where
+    C::Value: PartialOrd<C::Distance>,
+{
+    /// Create a MedianFrontier with the given dimensions and initial pixel locations.
+    pub fn new(
+        width: u32,
+        height: u32,
+        seeds: Vec<(u32, u32)>,
+        connectivity: Connectivity,
+    ) -> Self {
+        let median = C::geometric_median::<Vec<C>>;
+        Self {
+            inner: MeanFrontier::with_center(width, height, seeds, connectivity, median),
+        }
+    }
+}
+
+impl<C: ColorSpace> Frontier for MedianFrontier<C>
+where
+    C::Value: PartialOrd<C::Distance>,
+{
+    fn width(&self) -> u32 {
+        self.inner.width()
+    }
+
+    fn height(&self) -> u32 {
+        self.inner.height()
+    }
+
+    fn len(&self) -> usize {
+        self.inner.len()
+    }
+
+    fn place(&mut self, rgb8: Rgb8) -> Option<(u32, u32)> {
+        self.inner.place(rgb8)
+    }
+
+    fn place_debug(&mut self, rgb8: Rgb8) -> Option<Placement> {
+        self.inner.place_debug(rgb8)
+    }
+
+    fn pixel_state(&self, x: u32, y: u32) -> PixelState {
+        self.inner.pixel_state(x, y)
+    }
+
+    fn visited_pixels(&self) -> impl Iterator<Item = (u32, u32, Rgb8)> {
+        self.inner.visited_pixels()
+    }
+
+    fn statistics(&self) -> Statistics {
+        self.inner.statistics()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::color::LabSpace;
+
+    use rand::prelude::*;
+    use rand_pcg::Pcg64;
+
+    /// Paint some random colors, and return where they were placed.
+    fn paint(frontier: &mut impl Frontier) -> Vec<(u32, u32)> {
+        let mut rng = Pcg64::seed_from_u64(0);
+        (0..256)
+            .map(|_| Rgb8::from(rng.gen::<[u8; 3]>()))
+            .map_while(|rgb8| frontier.place(rgb8))
+            .collect()
+    }
+
+    #[test]
+    fn test_median_vs_mean() {
+        let seeds = vec![(8, 8)];
+        let connectivity = Connectivity::Eight;
+        let mut median = MedianFrontier::<LabSpace>::new(16, 16, seeds.clone(), connectivity);
+        let mut mean = MeanFrontier::<LabSpace>::new(16, 16, seeds, connectivity);
+
+        let median_placed = paint(&mut median);
+        let mean_placed = paint(&mut mean);
+        assert_eq!(median_placed.len(), 256);
+        assert_eq!(mean_placed.len(), 256);
+        assert!(median.is_empty());
+
+        assert_eq!(median_placed[0], mean_placed[0]);
+        assert_ne!(median_placed, mean_placed);
+    }
+}
@@ -1,6 +1,8 @@
 //! Minimum selection frontier.
 
-use super::{neighbors, Frontier, RcPixel, Target};
+use super::{
+    Connectivity, Frontier, Neighbors, PixelState, Placement, RcPixel, Statistics, Target,
+};
 
 use crate::color::{ColorSpace, Rgb8};
 use crate::forest::KdForest;
@@ -9,11 +11,13 @@ use acap::knn::NearestNeighbors;
 
 use rand::Rng;
 
+use std::collections::HashSet;
+
 /// A pixel on a min frontier.
 #[derive(Debug)]
 struct MinPixel<C> {
     pixel: Option<RcPixel<C>>,
-    filled: bool,
+    filled: Option<Rgb8>,
 }
 
 impl<C: ColorSpace> MinPixel<C>
@@ -23,7 +27,7 @@ where
     fn new() -> Self {
         Self {
             pixel: None,
-            filled: false,
+            filled: None,
         }
     }
 }
@@ -36,34 +40,64 @@ pub struct MinFrontier<C, R> {
     forest: KdForest<RcPixel<C>>,
     width: u32,
     height: u32,
-    x0: u32,
-    y0: u32,
+    seeds: Vec<(u32, u32)>,
+    connectivity: Connectivity,
+    wrap: bool,
     len: usize,
     deleted: usize,
+    stats: Statistics,
 }
 
 impl<C: ColorSpace, R: Rng> MinFrontier<C, R>
 where
     C::Value: PartialOrd<C::Distance>,
 {
-    /// Create a MinFrontier with the given dimensions and initial pixel location.
-    pub fn new(rng: R, width: u32, height: u32, x0: u32, y0: u32) -> Self {
+    /// Create a MinFrontier with the given dimensions and initial pixel locations.
+    pub fn new(
+        rng: R,
+        width: u32,
+        height: u32,
+        mut seeds: Vec<(u32, u32)>,
+        connectivity: Connectivity,
+    ) -> Self {
         let size = (width as usize) * (height as usize);
         let mut pixels = Vec::with_capacity(size);
         for _ in 0..size {
             pixels.push(MinPixel::new());
         }
 
+        // Seeds are placed in order, by popping them off the end
+        let mut seen = HashSet::new();
+        seeds.retain(|seed| seen.insert(*seed));
+        seeds.reverse();
+
         Self {
             rng,
             pixels,
             forest: KdForest::new(),
             width,
             height,
-            x0,
-            y0,
+            seeds,
+            connectivity,
+            wrap: false,
             len: 0,
             deleted: 0,
+            stats: Statistics::default(),
+        }
+    }
+
+    /// Make the image wrap around at the edges, like a torus.
+    pub(super) fn wrapping(mut self) -> Self {
+        self.wrap = true;
+        self
+    }
+
+    fn neighbors(&self, x: u32, y: u32) -> Neighbors {
+        let neighbors = self.connectivity.neighbors(x, y);
+        if self.wrap {
+            neighbors.wrap(self.width, self.height)
+        } else {
+            neighbors
         }
     }
 
@@ -78,12 +112,12 @@ where
         // Pick a pseudo-random neighbor
         let offset: usize = self.rng.gen();
 
-        let neighbors = neighbors(x, y);
-        for i in 0..8 {
-            let (x, y) = neighbors[(i + offset) % 8];
+        let neighbors = self.neighbors(x, y);
+        for i in 0..neighbors.len() {
+            let (x, y) = neighbors[(i + offset) % neighbors.len()];
             if x < self.width && y < self.height {
                 let i = self.pixel_index(x, y);
-                if !self.pixels[i].filled {
+                if self.pixels[i].filled.is_none() {
                     return Some((x, y));
                 }
             }
@@ -92,23 +126,23 @@ where
         None
     }
 
-    fn fill(&mut self, x: u32, y: u32, color: C) -> Option<(u32, u32)> {
+    fn fill(&mut self, x: u32, y: u32, rgb8: Rgb8, color: C) -> Option<(u32, u32)> {
         let i = self.pixel_index(x, y);
         let pixel = &mut self.pixels[i];
-        if pixel.filled {
+        if pixel.filled.is_some() {
             return None;
         }
 
         let rc = RcPixel::new(x, y, color);
         pixel.pixel = Some(rc.clone());
-        pixel.filled = true;
+        pixel.filled = Some(rgb8);
 
         if self.free_neighbor(x, y).is_some() {
             self.forest.push(rc);
             self.len += 1;
         }
 
-        for &(x, y) in &neighbors(x, y) {
+        for &(x, y) in self.neighbors(x, y).iter() {
             if x < self.width && y < self.height && self.free_neighbor(x, y).is_none() {
                 let i = self.pixel_index(x, y);
                 if let Some(pixel) = self.pixels[i].pixel.take() {
@@ -120,6 +154,43 @@ where
 
         Some((x, y))
     }
+
+    /// Find the closest filled pixel to a color, unless there are still seeds to fill.
+    fn nearest(&self, color: C) -> Option<((u32, u32), C, C::Distance)> {
+        if !self.seeds.is_empty() {
+            return None;
+        }
+
+        self.forest
+            .nearest(&Target(color))
+            .map(|n| (n.item.pos, n.item.color, n.distance))
+    }
+
+    /// Find the furthest filled pixel from a color, unless there are still seeds to fill.
+    pub(super) fn furthest(&self, color: C) -> Option<((u32, u32), C, C::Distance)> {
+        if !self.seeds.is_empty() {
+            return None;
+        }
+
+        self.forest
+            .furthest(&Target(color))
+            .map(|n| (n.item.pos, n.item.color, n.distance))
+    }
+
+    /// Place a color next to the given pixel, or on the next seed if there isn't one.
+    pub(super) fn place_near(
+        &mut self,
+        rgb8: Rgb8,
+        color: C,
+        near: Option<(u32, u32)>,
+    ) -> Option<(u32, u32)> {
+        let (x, y) = match near {
+            Some((x, y)) => self.free_neighbor(x, y).unwrap(),
+            None => self.seeds.pop()?,
+        };
+
+        self.fill(x, y, rgb8, color)
+    }
 }
 
 impl<C: ColorSpace, R: Rng> Frontier for MinFrontier<C, R>
@@ -140,13 +211,45 @@ where
 
     fn place(&mut self, rgb8: Rgb8) -> Option<(u32, u32)> {
         let color = C::from(rgb8);
-        let (x, y) = self
-            .forest
-            .nearest(&Target(color))
-            .map(|n| n.item.pos)
-            .map(|(x, y)| self.free_neighbor(x, y).unwrap())
-            .unwrap_or((self.x0, self.y0));
+        let nearest = self.nearest(color);
+        let pos = self.place_near(rgb8, color, nearest.map(|n| n.0))?;
+        self.stats.record(nearest.map(|n| n.2.into()), self.len());
+        Some(pos)
+    }
+
+    fn place_debug(&mut self, rgb8: Rgb8) -> Option<Placement> {
+        let color = C::from(rgb8);
+        let nearest = self.nearest(color);
+        let pos = self.place_near(rgb8, color, nearest.map(|n| n.0))?;
+        self.stats.record(nearest.map(|n| n.2.into()), self.len());
+        Some(Placement::new(self, rgb8, pos, nearest))
+    }
+
+    fn pixel_state(&self, x: u32, y: u32) -> PixelState {
+        let filled = |x: u32, y: u32| {
+            x < self.width
+                && y < self.height
+                && self.pixels[self.pixel_index(x, y)].filled.is_some()
+        };
+
+        if filled(x, y) {
+            PixelState::Filled
+        } else if self.neighbors(x, y).iter().any(|&(x, y)| filled(x, y)) {
+            PixelState::Frontier
+        } else {
+            PixelState::Empty
+        }
+    }
+
+    fn visited_pixels(&self) -> impl Iterator<Item = (u32, u32, Rgb8)> {
+        let width = self.width;
+        self.pixels.iter().enumerate().filter_map(move |(i, pixel)| {
+            let i = i as u32;
+            pixel.filled.map(|rgb8| (i % width, i / width, rgb8))
+        })
+    }
 
-        self.fill(x, y, color)
+    fn statistics(&self) -> Statistics {
+        self.stats
     }
 }
@@ -0,0 +1,101 @@
+//! Toroidal frontier.
+
+use super::min::MinFrontier;
+use super::{Connectivity, Frontier, PixelState, Placement, Statistics};
+
+use crate::color::{ColorSpace, Rgb8};
+
+use rand::Rng;
+
+/// A [MinFrontier] on an image that wraps around at the edges, like a torus.
+///
+/// The left edge is adjacent to the right edge, and the top edge to the bottom, so the generated
+/// images tile seamlessly.
+#[derive(Debug)]
+pub struct WrapFrontier<C, R> {
+    inner: MinFrontier<C, R>,
+}
+
+impl<C: ColorSpace, R: Rng> WrapFrontier<C, R>
+where
+    C::Value: PartialOrd<C::Distance>,
+{
+    /// Create a WrapFrontier with the given dimensions and initial pixel locations.
+    pub fn new(
+        rng: R,
+        width: u32,
+        height: u32,
+        seeds: Vec<(u32, u32)>,
+        connectivity: Connectivity,
+    ) -> Self {
+        Self {
+            inner: MinFrontier::new(rng, width, height, seeds, connectivity).wrapping(),
+        }
+    }
+}
+
+impl<C: ColorSpace, R: Rng> Frontier for WrapFrontier<C, R>
+where
+    C::Value: PartialOrd<C::Distance>,
+{
+    fn width(&self) -> u32 {
+        self.inner.width()
+    }
+
+    fn height(&self) -> u32 {
+        self.inner.height()
+    }
+
+    fn len(&self) -> usize {
+        self.inner.len()
+    }
+
+    fn place(&mut self, rgb8: Rgb8) -> Option<(u32, u32)> {
+        self.inner.place(rgb8)
+    }
+
+    fn place_debug(&mut self, rgb8: Rgb8) -> Option<Placement> {
+        self.inner.place_debug(rgb8)
+    }
+
+    fn pixel_state(&self, x: u32, y: u32) -> PixelState {
+        self.inner.pixel_state(x, y)
+    }
+
+    fn visited_pixels(&self) -> impl Iterator<Item = (u32, u32, Rgb8)> {
+        self.inner.visited_pixels()
+    }
+
+    fn statistics(&self) -> Statistics {
+        self.inner.statistics()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::color::LabSpace;
+
+    use rand::SeedableRng;
+    use rand_pcg::Pcg64;
+
+    #[test]
+    fn test_wrap() {
+        let rng = Pcg64::seed_from_u64(0);
+        let seeds = vec![(0, 0)];
+        let mut frontier = WrapFrontier::<LabSpace, _>::new(rng, 8, 8, seeds, Connectivity::Eight);
+
+        assert_eq!(frontier.place(Rgb8::from([0, 0, 0])), Some((0, 0)));
+        assert_eq!(frontier.pixel_state(7, 0), PixelState::Frontier);
+        assert_eq!(frontier.pixel_state(0, 7), PixelState::Frontier);
+        assert_eq!(frontier.pixel_state(7, 7), PixelState::Frontier);
+        assert_eq!(frontier.pixel_state(2, 0), PixelState::Empty);
+
+        for i in 0..63 {
+            assert!(frontier.place(Rgb8::from([4 * i, 0, 0])).is_some());
+        }
+        assert_eq!(frontier.place(Rgb8::from([255, 255, 255])), None);
+        assert_eq!(frontier.visited_pixels().count(), 64);
+    }
+}
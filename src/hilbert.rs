@@ -44,6 +44,11 @@ fn intra_direction(i: usize) -> u32 {
     }
 }
 
+/// T transformation
+fn t_transform(dims: u32, e: usize, d: u32, b: usize) -> usize {
+    rotate_right(b ^ e, d, dims)
+}
+
 /// T transformation inverse
 fn t_inverse(dims: u32, e: usize, d: u32, a: usize) -> usize {
     rotate_left(a, d, dims) ^ e
@@ -77,6 +82,28 @@ fn gray_code_rank_inverse(
     (i, g)
 }
 
+/// The inverse of the binary reflected Gray code.
+fn gray_code_inverse(g: usize) -> usize {
+    let mut i = 0;
+    let mut g = g;
+    while g != 0 {
+        i ^= g;
+        g >>= 1;
+    }
+    i
+}
+
+/// GrayCodeRank
+fn gray_code_rank(dims: u32, mu: usize, w: usize) -> usize {
+    let mut r = 0;
+    for k in (0..dims).rev() {
+        if mu & (1 << k) != 0 {
+            r = (r << 1) | ((w >> k) & 1);
+        }
+    }
+    r
+}
+
 /// ExtractMask.
 fn extract_mask(bits: &[u32], i: u32) -> (usize, u32) {
     // The mask
@@ -130,7 +157,262 @@ pub fn hilbert_point(index: usize, bits: &[u32], point: &mut [usize]) {
             l >>= 1;
         }
 
-        e ^= rotate_right(entry_point(w), d, dims);
+        e ^= rotate_left(entry_point(w), d, dims);
+        d = (d + intra_direction(w) + 1) % dims;
+    }
+}
+
+/// Compute the Hilbert index of a point (CompactHilbertIndex).
+///
+/// This is the inverse of [hilbert_point].
+pub fn hilbert_index(point: &[usize], bits: &[u32]) -> usize {
+    let dims = bits.len() as u32;
+    let max = *bits.iter().max().unwrap();
+
+    let mut e = 0;
+    let mut h = 0;
+
+    // Next direction; we use d instead of d + 1 everywhere
+    let mut d = 1;
+
+    for i in (0..max).rev() {
+        let (mut mu, free_bits) = extract_mask(bits, i);
+        mu = rotate_right(mu, d, dims);
+
+        let mut l = 0;
+        for (j, x) in point.iter().enumerate() {
+            l |= ((x >> i) & 1) << j;
+        }
+
+        let t = t_transform(dims, e, d, l);
+        let w = gray_code_inverse(t);
+        let r = gray_code_rank(dims, mu, w);
+
+        e ^= rotate_left(entry_point(w), d, dims);
         d = (d + intra_direction(w) + 1) % dims;
+
+        h = (h << free_bits) | r;
+    }
+
+    h
+}
+
+/// Compute the corresponding point for an index on a 2D Hilbert curve with a side length of `n`.
+///
+/// The curve starts at `(0, 0)` and ends at `(n - 1, 0)`.
+fn hilbert_point_2d(index: usize, n: usize) -> (usize, usize) {
+    let (mut x, mut y) = (0, 0);
+    let mut t = index;
+
+    let mut s = 1;
+    while s < n {
+        let rx = 1 & (t / 2);
+        let ry = 1 & (t ^ rx);
+        if ry == 0 {
+            if rx == 1 {
+                x = s - 1 - x;
+                y = s - 1 - y;
+            }
+            std::mem::swap(&mut x, &mut y);
+        }
+        x += s * rx;
+        y += s * ry;
+        t /= 4;
+        s *= 2;
+    }
+
+    (x, y)
+}
+
+/// Compute the corresponding point for an index on a 2D [Moore curve] with a side length of
+/// `1 << bits`.
+///
+/// A Moore curve is made of four Hilbert curves, arranged so that it ends next to where it
+/// started.
+///
+/// [Moore curve]: https://en.wikipedia.org/wiki/Moore_curve
+pub fn moore_point(index: usize, bits: u32, point: &mut [usize]) {
+    if bits == 0 {
+        point[0] = 0;
+        point[1] = 0;
+        return;
+    }
+
+    let s = 1 << (bits - 1);
+    let quad_bits = 2 * (bits - 1);
+    let quad = index >> quad_bits;
+    let (x, y) = hilbert_point_2d(index & ((1 << quad_bits) - 1), s);
+
+    // The left quadrants run up, and the right quadrants run down
+    let (x, y) = if quad < 2 { (s - 1 - y, x) } else { (y, s - 1 - x) };
+
+    point[0] = if quad < 2 { x } else { x + s };
+    point[1] = if quad == 1 || quad == 2 { y + s } else { y };
+}
+
+/// The Euclidean distance between two points.
+fn point_distance(a: &[usize], b: &[usize]) -> f64 {
+    a.iter()
+        .zip(b)
+        .map(|(x, y)| x.abs_diff(*y) as f64)
+        .map(|d| d * d)
+        .sum::<f64>()
+        .sqrt()
+}
+
+/// Compute the total Euclidean arc length of the Hilbert curve with the given bit depths.
+///
+/// Every step of a Hilbert curve has unit length, so for a curve through `n` points this is
+/// `n - 1`.
+pub fn hilbert_path_length(bits: &[u32]) -> f64 {
+    let sum: u32 = bits.iter().sum();
+    let size = 1usize << sum;
+
+    let mut prev = vec![0; bits.len()];
+    let mut point = vec![0; bits.len()];
+    let mut length = 0.0;
+
+    for i in 0..size {
+        hilbert_point(i, bits, &mut point);
+        if i > 0 {
+            length += point_distance(&prev, &point);
+        }
+        std::mem::swap(&mut prev, &mut point);
+    }
+
+    length
+}
+
+/// The largest index offset considered by [path_locality].
+const MAX_LOCALITY_OFFSET: usize = 256;
+
+/// Measure the locality of a path through `len` points.
+///
+/// Returns pairs `(k, d)`, where `d` is the average Euclidean distance between the points with
+/// indices `i` and `i + k`, for `k` in powers of two.  Smaller distances mean better locality.
+pub fn path_locality<I, P>(points: I, len: usize) -> Vec<(usize, f64)>
+where
+    I: IntoIterator<Item = P>,
+    P: AsRef<[f64]>,
+{
+    let offsets: Vec<_> = (0..)
+        .map(|j| 1usize << j)
+        .take_while(|k| *k < len && *k <= MAX_LOCALITY_OFFSET)
+        .collect();
+    let window = offsets.last().map_or(1, |k| k + 1);
+
+    let distance = |a: &[f64], b: &[f64]| {
+        a.iter().zip(b).map(|(x, y)| (x - y) * (x - y)).sum::<f64>().sqrt()
+    };
+
+    // A ring buffer of the most recent points
+    let mut recent = Vec::with_capacity(window);
+    let mut totals = vec![0.0; offsets.len()];
+
+    for (i, point) in points.into_iter().take(len).enumerate() {
+        if recent.len() < window {
+            recent.push(point);
+        } else {
+            recent[i % window] = point;
+        }
+
+        let point = recent[i % window].as_ref();
+        for (k, total) in offsets.iter().zip(totals.iter_mut()) {
+            if i >= *k {
+                *total += distance(point, recent[(i - k) % window].as_ref());
+            }
+        }
+    }
+
+    offsets
+        .into_iter()
+        .zip(totals)
+        .map(|(k, total)| (k, total / (len - k) as f64))
+        .collect()
+}
+
+/// Measure the locality of the Hilbert curve with the given bit depths, with [path_locality].
+pub fn hilbert_path_locality(bits: &[u32]) -> Vec<(usize, f64)> {
+    let sum: u32 = bits.iter().sum();
+    let size = 1usize << sum;
+
+    let mut point = vec![0; bits.len()];
+    let points = (0..size).map(|i| {
+        hilbert_point(i, bits, &mut point);
+        point.iter().map(|&c| c as f64).collect::<Vec<_>>()
+    });
+
+    path_locality(points, size)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_moore_point() {
+        let mut point = [0, 0];
+        let points: Vec<_> = (0..16)
+            .map(|i| {
+                moore_point(i, 2, &mut point);
+                (point[0], point[1])
+            })
+            .collect();
+
+        #[rustfmt::skip]
+        let expected = [
+            (1, 0), (0, 0), (0, 1), (1, 1),
+            (1, 2), (0, 2), (0, 3), (1, 3),
+            (2, 3), (3, 3), (3, 2), (2, 2),
+            (2, 1), (3, 1), (3, 0), (2, 0),
+        ];
+        assert_eq!(points, expected);
+
+        // The curve is continuous and closed
+        for bits in 1..6 {
+            let size = 1 << (2 * bits);
+            let mut prev = [0, 0];
+            moore_point(size - 1, bits, &mut prev);
+            for i in 0..size {
+                moore_point(i, bits, &mut point);
+                assert_eq!(point_distance(&prev, &point), 1.0, "{} {:?}", bits, point);
+                prev = point;
+            }
+        }
+    }
+
+    #[test]
+    fn test_index_inverse() {
+        for bits in [vec![4, 4], vec![3, 3, 3], vec![3, 2, 1], vec![1, 4, 2, 3]] {
+            let sum: u32 = bits.iter().sum();
+            let mut point = vec![0; bits.len()];
+            for i in 0..(1 << sum) {
+                hilbert_point(i, &bits, &mut point);
+                assert_eq!(hilbert_index(&point, &bits), i, "{:?} {:?}", bits, point);
+            }
+        }
+    }
+
+    #[test]
+    fn test_path_length() {
+        assert_eq!(hilbert_path_length(&[4, 4, 4]), 4095.0);
+        assert_eq!(hilbert_path_length(&[3, 3]), 63.0);
+    }
+
+    #[test]
+    fn test_path_locality() {
+        let locality = hilbert_path_locality(&[4, 4]);
+        assert_eq!(locality.len(), 8);
+        assert_eq!(locality[0], (1, 1.0));
+        for pair in locality.windows(2) {
+            assert!(pair[0].1 <= pair[1].1);
+        }
+    }
+
+    #[test]
+    fn test_path_locality_line() {
+        let points = (0..10).map(|i| [i as f64, 0.0]);
+        assert_eq!(path_locality(points, 10), [(1, 1.0), (2, 2.0), (4, 4.0), (8, 8.0)]);
+        assert!(path_locality([[0.0; 2]], 1).is_empty());
     }
 }
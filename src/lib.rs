@@ -0,0 +1,7 @@
+pub mod apng;
+pub mod color;
+pub mod forest;
+pub mod frontier;
+pub mod hilbert;
+pub mod peano;
+pub mod tile;
@@ -1,38 +1,62 @@
-pub mod color;
-pub mod forest;
-pub mod frontier;
-pub mod hilbert;
-
-use crate::color::source::{AllColors, ColorSource, ImageColors};
-use crate::color::{order, ColorSpace, LabSpace, LuvSpace, OklabSpace, Rgb8, RgbSpace};
-use crate::frontier::image::ImageFrontier;
-use crate::frontier::mean::MeanFrontier;
-use crate::frontier::min::MinFrontier;
-use crate::frontier::Frontier;
+use kd_forest::apng::write_apng;
+use kd_forest::color::source::{
+    AllColors, ColorSource, GoldenRatioColors, GradientColors, HasPositions, ImageColors,
+    PaletteColors,
+};
+use kd_forest::color::{
+    order, rgb8_from_hex, set_peak_luminance, ChannelWeights, ColorSpace, Din99Space, HslSpace,
+    HsvSpace, IctcpSpace, JzazbzSpace, LabSpace, LchSpace, LuvSpace, OklabSpace, OklchSpace, Rgb8,
+    RgbSpace, WeightedSpace,
+};
+use kd_forest::frontier::fountain::FountainFrontier;
+use kd_forest::frontier::image::{ImageFrontier, REBUILD_RATIO};
+use kd_forest::frontier::max::MaxFrontier;
+use kd_forest::frontier::mean::MeanFrontier;
+use kd_forest::frontier::median::MedianFrontier;
+use kd_forest::frontier::min::MinFrontier;
+use kd_forest::frontier::wrap::WrapFrontier;
+use kd_forest::frontier::{Connectivity, Frontier};
+use kd_forest::hilbert::{hilbert_path_length, hilbert_path_locality};
+use kd_forest::tile::{paint_tiles, TileSeeds};
 
 use clap::{ArgAction, CommandFactory, Parser, ValueEnum};
 use clap::error::ErrorKind;
 
-use image::{self, ColorType, ImageEncoder, ImageError, Rgba, RgbaImage};
+use image::{self, ColorType, ImageEncoder, ImageError, ImageFormat, Rgba, RgbImage, RgbaImage};
+use image::buffer::ConvertBuffer;
+use image::codecs::jpeg::JpegEncoder;
 use image::codecs::png::{CompressionType, FilterType, PngEncoder};
+use image::codecs::webp::WebPEncoder;
 
 use rand::{self, SeedableRng};
 use rand_pcg::Pcg64;
 
 use std::cmp;
 use std::error::Error;
-use std::io::{self, BufWriter, IsTerminal, Write};
-use std::path::PathBuf;
+use std::fs::File;
+use std::io::{self, BufWriter, IsTerminal, StdoutLock, Write};
+use std::path::{Path, PathBuf};
 use std::process::exit;
+use std::sync::OnceLock;
 use std::time::Instant;
 
 /// The color source specified on the command line.
-#[derive(Debug, Eq, PartialEq)]
+#[derive(Debug, PartialEq)]
 enum SourceArg {
     /// All RGB colors of the given bit depth(s).
     AllRgb(u32, u32, u32),
+    /// The N most distinct colors of the given bit depth(s), in the given color space.
+    Palette(u32, u32, u32, usize, ColorSpaceArg),
+    /// N random colors of the given bit depth(s).
+    Sample(u32, u32, u32, usize),
+    /// A palette of N colors with golden-ratio hue steps, and the given saturation and lightness.
+    GoldenRatio(usize, f64, f64),
+    /// A gradient of N colors between two endpoints.
+    Gradient(Rgb8, Rgb8, usize),
     /// Take the colors from an image.
     Image(PathBuf),
+    /// Use each pixel of an image as one color of a palette.
+    PaletteFile(PathBuf),
 }
 
 /// The order to process colors in.
@@ -40,12 +64,36 @@ enum SourceArg {
 enum OrderArg {
     /// Sorted by hue.
     HueSort,
+    /// Sorted by saturation, most saturated first.
+    SaturationSort,
+    /// Sorted by luminance, darkest first.
+    LuminanceSort,
     /// Shuffled randomly.
     Random,
     /// Morton/Z-order.
     Morton,
+    /// Morton/Z-order, specialized for 3D sources.
+    Morton3d,
     /// Hilbert curve order.
     Hilbert,
+    /// 3D Hilbert curve order through the color cube.
+    Hilbert3d,
+    /// Moore curve order.
+    Moore,
+    /// Peano curve order.
+    Peano,
+    /// Anti-diagonal order.
+    Diagonal,
+    /// Checkerboard order.
+    Checkerboard,
+    /// Whichever order has the best locality.
+    Auto,
+    /// Van der Corput (bit-reversed Morton) order.
+    VanDerCorput,
+    /// Hilbert curve order of the colors' positions in the source image.
+    SourcePosition,
+    /// Most frequent colors first, then by hue.
+    FrequencyHue,
 }
 
 /// The frontier implementation.
@@ -55,51 +103,227 @@ enum FrontierArg {
     Min,
     /// Pick the pixel with the closest mean color of all its neighbors.
     Mean,
+    /// Pick a neighbor of the furthest pixel so far.
+    Max,
+    /// Pick the pixel with the closest geometric median color of all its neighbors.
+    Median,
+    /// Fill rings of pixels around the seeds, picking the pixel in the current ring with the
+    /// closest mean color of its neighbors.
+    Fountain,
     /// Target the closest pixel on an image.
     #[value(skip)]
     Image(PathBuf),
 }
 
+/// Where to start painting each tile.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, ValueEnum)]
+enum TileSeedArg {
+    /// The center of each tile.
+    Center,
+    /// A random pixel in each tile.
+    Random,
+    /// The four corners of each tile.
+    Corners,
+}
+
+impl From<TileSeedArg> for TileSeeds {
+    fn from(arg: TileSeedArg) -> Self {
+        match arg {
+            TileSeedArg::Center => Self::Center,
+            TileSeedArg::Random => Self::Random,
+            TileSeedArg::Corners => Self::Corners,
+        }
+    }
+}
+
 /// The color space to operate in.
 #[derive(Clone, Copy, Debug, Eq, PartialEq, ValueEnum)]
 enum ColorSpaceArg {
     /// sRGB space.
     #[value(name = "RGB")]
     Rgb,
+    /// HSL space.
+    #[value(name = "HSL")]
+    Hsl,
+    /// HSV space.
+    #[value(name = "HSV")]
+    Hsv,
     /// CIE L*a*b* space.
     #[value(name = "Lab")]
     Lab,
+    /// CIE LCh space, the cylindrical form of L*a*b*.
+    #[value(name = "LCh")]
+    Lch,
+    /// DIN99 space.
+    #[value(name = "DIN99")]
+    Din99,
     /// CIE L*u*v* space.
     #[value(name = "Luv")]
     Luv,
     /// Oklab space.
     #[value(name = "Oklab")]
     Oklab,
+    /// Oklch space, the cylindrical form of Oklab.
+    #[value(name = "OKLCh")]
+    Oklch,
+    /// JzAzBz space.
+    #[value(name = "JzAzBz")]
+    Jzazbz,
+    /// ICtCp space.
+    #[value(name = "ICtCp")]
+    Ictcp,
+}
+
+impl ColorSpaceArg {
+    /// Whether this space's channels include a hue angle, which its coordinates don't match.
+    fn is_cylindrical(self) -> bool {
+        matches!(self, Self::Hsl | Self::Hsv | Self::Lch | Self::Oklch)
+    }
+}
+
+/// The image format to save.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, ValueEnum)]
+enum OutputFormatArg {
+    /// Portable Network Graphics.
+    Png,
+    /// Lossless WebP.
+    Webp,
+    /// Lossy JPEG, without transparency.
+    #[value(alias = "jpg")]
+    Jpeg,
+}
+
+impl OutputFormatArg {
+    /// Guess the format from a file name.
+    fn from_path(path: &Path) -> Self {
+        match path.extension() {
+            Some(ext) if ext.eq_ignore_ascii_case("webp") => Self::Webp,
+            Some(ext) if ext.eq_ignore_ascii_case("jpg") || ext.eq_ignore_ascii_case("jpeg") => {
+                Self::Jpeg
+            }
+            _ => Self::Png,
+        }
+    }
+
+    /// The usual file extension for this format.
+    fn extension(self) -> &'static str {
+        match self {
+            Self::Png => "png",
+            Self::Webp => "webp",
+            Self::Jpeg => "jpg",
+        }
+    }
+}
+
+/// The --color-weights, if any.
+static COLOR_WEIGHTS: OnceLock<[f64; 3]> = OnceLock::new();
+
+/// The [ChannelWeights] specified on the command line.
+#[derive(Clone, Copy, Debug)]
+struct CliWeights;
+
+impl ChannelWeights for CliWeights {
+    fn weights() -> [f64; 3] {
+        COLOR_WEIGHTS.get().copied().unwrap_or([1.0; 3])
+    }
 }
 
 /// k-d forests.
 #[derive(Debug, Parser)]
 #[command(author, version, about, disable_help_flag = true)]
 struct Cli {
-    /// Use all <DEPTH>-bit colors.
+    /// Use all <DEPTH>-bit colors.  The bits are split between the channels by perceptual
+    /// importance, unless <DEPTH> is given as N,uniform or R,G,B.
     #[arg(short, long, group = "source", value_name = "DEPTH", default_value = "24")]
     bit_depth: Option<String>,
+    /// Use <R> bits of red.  Requires --green-bits and --blue-bits, and overrides --bit-depth.
+    #[arg(long, value_name = "R", requires_all = ["green_bits", "blue_bits"])]
+    #[arg(conflicts_with = "source")]
+    red_bits: Option<u32>,
+    /// Use <G> bits of green.
+    #[arg(long, value_name = "G", requires_all = ["red_bits", "blue_bits"])]
+    green_bits: Option<u32>,
+    /// Use <B> bits of blue.
+    #[arg(long, value_name = "B", requires_all = ["red_bits", "green_bits"])]
+    blue_bits: Option<u32>,
     /// use colors from the <INPUT> image.
     #[arg(short, long, group = "source", value_name = "INPUT")]
     input: Option<PathBuf>,
+    /// Use each distinct color of the <INPUT> image only once.
+    #[arg(long, requires = "input")]
+    deduplicate: bool,
+    /// Use the <N> most distinct colors of the given bit depth.
+    #[arg(long, value_name = "N")]
+    #[arg(conflicts_with_all = ["input", "golden_palette", "palette_file", "gradient"])]
+    palette: Option<usize>,
+    /// Use <N> random colors of the given bit depth.
+    #[arg(long, value_name = "N")]
+    #[arg(conflicts_with_all = ["input", "golden_palette", "palette_file", "gradient", "palette"])]
+    sample: Option<usize>,
+    /// The color space to use when picking --palette colors.
+    #[arg(long, value_name = "SPACE", default_value = "Lab")]
+    palette_space: ColorSpaceArg,
+    /// Use <N> colors with hues spaced by the golden ratio, and saturation <S> and lightness <L>.
+    #[arg(long, group = "source", value_name = "N,S,L")]
+    golden_palette: Option<String>,
+    /// Use a gradient of <N> colors from <FROM> to <TO>, given as hex colors like #FF8800.
+    #[arg(long, group = "source", value_name = "FROM,TO,N")]
+    gradient: Option<String>,
+    /// Use a palette of distinct colors, one per pixel of the <FILE> image.
+    #[arg(long, group = "source", value_name = "FILE")]
+    palette_file: Option<PathBuf>,
 
     /// Sort colors by hue [default].
     #[arg(short = 's', long, group = "order", default_value_t = true)]
     hue_sort: bool,
+    /// Sort colors from most to least saturated.
+    #[arg(short = 'S', long, group = "order")]
+    saturation_sort: bool,
+    /// Sort colors from darkest to brightest.
+    #[arg(short = 'L', long, group = "order")]
+    luminance_sort: bool,
     /// Randomize colors.
     #[arg(short, long, group = "order")]
     random: bool,
     /// Place colors in Morton order (Z-order).
     #[arg(short = 'M', long, group = "order")]
     morton: bool,
+    /// Place colors in Morton order, with a faster implementation for the RGB cube.
+    #[arg(long, group = "order")]
+    morton3d: bool,
     /// Place colors in Hilbert curve order
     #[arg(short = 'H', long, group = "order")]
     hilbert: bool,
+    /// Place colors in Hilbert curve order through the whole RGB cube.
+    #[arg(long, group = "order")]
+    hilbert3d: bool,
+    /// Place colors in Moore curve order, for square images.
+    #[arg(short = 'm', long, group = "order")]
+    moore: bool,
+    /// Place colors in Peano curve order.
+    #[arg(long, group = "order")]
+    peano: bool,
+    /// Place colors along anti-diagonals of the color space.
+    #[arg(short = 'd', long, group = "order")]
+    diagonal: bool,
+    /// Place colors like the squares of a checkerboard, all the even squares first.
+    #[arg(long, group = "order")]
+    checkerboard: bool,
+    /// Pick whichever of the above orders has the best locality.
+    #[arg(long, group = "order")]
+    auto_order: bool,
+    /// Place colors in van der Corput order, a quasi-random, low-discrepancy order.
+    #[arg(long, group = "order")]
+    van_der_corput: bool,
+    /// Place colors in Hilbert curve order of their positions in the <INPUT> image.
+    #[arg(long, group = "order", requires = "input")]
+    source_position_order: bool,
+    /// Place each distinct color of the <INPUT> image once, most frequent first, then by hue.
+    #[arg(long, group = "order", requires = "input")]
+    freq_hue: bool,
+    /// Reverse the order of the colors.
+    #[arg(short = 'R', long, conflicts_with = "random")]
+    reverse: bool,
 
     /// Reduce artifacts by iterating through the colors in multiple stripes [default].
     #[arg(short = 't', long, group = "stripe?", default_value_t = true)]
@@ -111,6 +335,21 @@ struct Cli {
     /// Specify the selection mode.
     #[arg(short = 'l', long, group = "frontier", value_name = "MODE", default_value = "min")]
     selection: FrontierArg,
+    /// Only grow the frontier up, down, left, and right, not diagonally.
+    #[arg(short = '4', long, conflicts_with = "target")]
+    four_connect: bool,
+    /// Wrap the image around at the edges, so that it tiles seamlessly.
+    #[arg(long, conflicts_with = "target")]
+    wrap: bool,
+    /// Paint the image as independent <W>x<H> tiles, in parallel, with --selection min.  This is
+    /// faster, but the seams between the tiles will be visible.
+    #[arg(long, value_name = "W,H")]
+    #[arg(conflicts_with_all = ["target", "wrap", "x0", "y0", "animate", "apng", "debug_pixel"])]
+    #[arg(conflicts_with_all = ["benchmark_mode", "progress_json"])]
+    tile_size: Option<String>,
+    /// Where to start painting each tile.
+    #[arg(long, value_name = "PATTERN", default_value = "center", requires = "tile_size")]
+    tile_seed_pattern: TileSeedArg,
     /// Place colors on the closest pixels of the <TARGET> image.
     #[arg(short = 'g', long, group = "frontier", value_name = "TARGET")]
     target: Option<PathBuf>,
@@ -118,6 +357,13 @@ struct Cli {
     /// Use the given color space.
     #[arg(short, long, value_name = "SPACE", default_value = "Lab")]
     color_space: ColorSpaceArg,
+    /// Weight the three channels of the color space by <W1>, <W2>, and <W3> when comparing colors.
+    /// Not supported for the cylindrical spaces (HSL, HSV, LCh, and OKLCh).
+    #[arg(long, value_name = "W1,W2,W3")]
+    color_weights: Option<String>,
+    /// Treat sRGB white as <NITS> cd/m² in the JzAzBz and ICtCp color spaces [default: 203].
+    #[arg(long, value_name = "NITS")]
+    peak_luminance: Option<f64>,
 
     /// The width of the generated image.
     #[arg(short, long)]
@@ -126,25 +372,86 @@ struct Cli {
     #[arg(short, long)]
     height: Option<u32>,
 
-    /// The x coordinate of the first pixel.
+    /// The x coordinate of the first pixel.  Repeat to start from multiple pixels.
     #[arg(short, value_name = "X")]
-    x0: Option<u32>,
-    /// The y coordinate of the first pixel.
+    x0: Vec<u32>,
+    /// The y coordinate of the first pixel.  Repeat to start from multiple pixels.
     #[arg(short, value_name = "Y")]
-    y0: Option<u32>,
+    y0: Vec<u32>,
+
+    /// Measure how often placements skip over already-filled target pixels.
+    #[arg(long, requires = "target")]
+    measure_rejection: bool,
+    /// Let colors far from their target pixel only partially cover it.
+    #[arg(long, value_name = "PERCENT", requires = "target")]
+    #[arg(value_parser = clap::value_parser!(u8).range(0..=100))]
+    target_softness: Option<u8>,
+    /// Rebuild the target's search trees once this fraction of its pixels has been filled.
+    #[arg(long, value_name = "RATIO", requires = "target", group = "rebuild")]
+    rebuild_ratio: Option<f64>,
+    /// Never rebuild the target's search trees.
+    #[arg(long, requires = "target", group = "rebuild")]
+    no_rebuild: bool,
+
+    /// Log details about the color placed at pixel (<X>, <Y>).
+    #[arg(long, value_name = "X,Y")]
+    debug_pixel: Option<String>,
 
     /// Generate frames of an animation.
     #[arg(short, long)]
     animate: bool,
-
-    /// Save the image to <PATH>.
-    #[arg(short, long, value_name = "PATH", default_value = "kd-forest.png")]
-    output: PathBuf,
-
-    /// Seed the random number generator.
+    /// Save an animated PNG to <PATH>.
+    #[arg(long, value_name = "PATH", conflicts_with = "animate")]
+    apng: Option<PathBuf>,
+    /// Write animation frames as raw RGBA pixels rather than PNGs.
+    #[arg(long, requires = "animate")]
+    raw_output: bool,
+    /// Show each frame of the animated PNG for <MS> milliseconds.
+    #[arg(long, value_name = "MS", default_value_t = 50, requires = "apng")]
+    frame_delay: u16,
+
+    /// Time how long it takes to place <N> pixels, print the throughput and peak memory, and exit.
+    #[arg(long, value_name = "N")]
+    benchmark_mode: Option<usize>,
+    /// Place <W> pixels before starting the benchmark timer.
+    #[arg(long, value_name = "W", default_value_t = 0, requires = "benchmark_mode")]
+    benchmark_warmup: usize,
+
+    /// Fill the pixels that no color is placed on with <HEX>, rather than leaving them transparent.
+    #[arg(long, value_name = "HEX")]
+    background_color: Option<String>,
+
+    /// Save the image to <PATH> [default: kd-forest.png].
+    #[arg(short, long, value_name = "PATH")]
+    output: Option<PathBuf>,
+    /// Save the image in the given format [default: from the <PATH> extension, or PNG].
+    #[arg(long, visible_alias = "output-format", value_name = "FORMAT")]
+    format: Option<OutputFormatArg>,
+    /// Save JPEG images with quality <Q>, from 1 to 100.  Transparent pixels become black, unless
+    /// --background-color is given.
+    #[arg(long, value_name = "Q", default_value_t = 90)]
+    #[arg(value_parser = clap::value_parser!(u8).range(1..=100))]
+    jpeg_quality: u8,
+
+    /// Use <N> threads for the parts of the generation that run in parallel [default: all CPUs].
+    #[arg(long, value_name = "N")]
+    #[arg(value_parser = clap::value_parser!(u16).range(1..=1024))]
+    num_threads: Option<u16>,
+    /// Use a single thread, like --num-threads 1.
+    #[arg(long, conflicts_with = "num_threads")]
+    single_thread: bool,
+
+    /// Seed the random number generator.  The same seed always produces the same image.
     #[arg(short = 'e', long, default_value_t = 0)]
     seed: u64,
 
+    /// Print extra information about the generation process.
+    #[arg(short, long)]
+    verbose: bool,
+    /// Report progress as one JSON object per line.
+    #[arg(long)]
+    progress_json: bool,
+
     /// Print help.
     #[arg(short = '?', long, action = ArgAction::Help)]
     help: (),
@@ -193,6 +500,18 @@ impl From<io::Error> for AppError {
     }
 }
 
+impl From<png::EncodingError> for AppError {
+    fn from(err: png::EncodingError) -> Self {
+        Self::RuntimeError(Box::new(err))
+    }
+}
+
+impl From<rayon::ThreadPoolBuildError> for AppError {
+    fn from(err: rayon::ThreadPoolBuildError) -> Self {
+        Self::RuntimeError(Box::new(err))
+    }
+}
+
 impl From<rand::Error> for AppError {
     fn from(err: rand::Error) -> Self {
         Self::RuntimeError(Box::new(err))
@@ -202,45 +521,162 @@ impl From<rand::Error> for AppError {
 /// Result type for this app.
 type AppResult<T> = Result<T, AppError>;
 
+/// The kind of animation to generate.
+#[derive(Debug, PartialEq)]
+enum AnimationArg {
+    /// Write each frame to standard output as a PNG.
+    Frames,
+    /// Write each frame to standard output as raw RGBA pixels, in row-major order.
+    RawFrames,
+    /// Write an animated PNG to the given path, with the given frame delay in milliseconds.
+    Apng(PathBuf, u16),
+}
+
+/// Added to the seed to derive the frontier's random number generator.
+const FRONTIER_SEED_OFFSET: u64 = 0xdeadbeef;
+
+/// The peak resident set size of this process in KiB, where the OS reports it (i.e. on Linux).
+fn peak_rss_kib() -> Option<u64> {
+    let status = std::fs::read_to_string("/proc/self/status").ok()?;
+    let line = status.lines().find_map(|line| line.strip_prefix("VmHWM:"))?;
+    line.trim().strip_suffix("kB")?.trim().parse().ok()
+}
+
+/// The largest image we're willing to generate, to avoid running out of memory.
+const MAX_PIXELS: u64 = 1 << 30;
+
+/// Check that an image size is reasonable.
+fn check_dimensions(width: u32, height: u32) -> AppResult<()> {
+    if width == 0 || height == 0 {
+        return Err(AppError::invalid_value(
+            &format!("Image size {}x{} is empty", width, height),
+        ));
+    }
+
+    let pixels = u64::from(width) * u64::from(height);
+    if pixels > MAX_PIXELS {
+        return Err(AppError::invalid_value(&format!(
+            "Image size {}x{} has {} pixels, more than the limit of {}",
+            width, height, pixels, MAX_PIXELS,
+        )));
+    }
+
+    Ok(())
+}
+
 /// The parsed command line arguments.
 #[derive(Debug)]
 struct Args {
     source: SourceArg,
+    deduplicate: bool,
     order: OrderArg,
+    reverse: bool,
     stripe: bool,
     frontier: FrontierArg,
+    connectivity: Connectivity,
+    wrap: bool,
+    tiles: Option<((u32, u32), TileSeeds)>,
     space: ColorSpaceArg,
+    weights: Option<[f64; 3]>,
+    peak_luminance: Option<f64>,
     width: Option<u32>,
     height: Option<u32>,
-    x0: Option<u32>,
-    y0: Option<u32>,
-    animate: bool,
+    x0: Vec<u32>,
+    y0: Vec<u32>,
+    debug_pixel: Option<(u32, u32)>,
+    measure_rejection: bool,
+    target_softness: u8,
+    rebuild_ratio: Option<f64>,
+    animate: Option<AnimationArg>,
+    benchmark: Option<(usize, usize)>,
+    background: Option<Rgb8>,
     output: PathBuf,
+    format: OutputFormatArg,
+    jpeg_quality: u8,
+    num_threads: Option<usize>,
     seed: u64,
+    verbose: bool,
+    progress_json: bool,
 }
 
 impl Args {
     fn parse() -> AppResult<Self> {
-        let args = Cli::try_parse()?;
+        Self::from_cli(Cli::try_parse()?)
+    }
+
+    fn from_cli(args: Cli) -> AppResult<Self> {
 
         let source = if let Some(input) = args.input {
             SourceArg::Image(input)
+        } else if let Some(path) = args.palette_file {
+            SourceArg::PaletteFile(path)
+        } else if let Some(arg) = args.gradient {
+            let params: Vec<_> = arg.split(',').collect();
+
+            let (from, to, n) = match params.as_slice() {
+                [from, to, n] => (rgb8_from_hex(from).ok(), rgb8_from_hex(to).ok(), n.parse().ok()),
+                _ => (None, None, None),
+            };
+
+            match (from, to, n) {
+                (Some(from), Some(to), Some(n)) if n > 0 => SourceArg::Gradient(from, to, n),
+                _ => {
+                    return Err(AppError::invalid_value(&format!("invalid gradient {}", arg)));
+                }
+            }
+        } else if let Some(arg) = args.golden_palette {
+            let params: Vec<_> = arg.split(',').collect();
+
+            let (n, s, l) = match params.as_slice() {
+                [n, s, l] => (n.parse().ok(), s.parse().ok(), l.parse().ok()),
+                _ => (None, None, None),
+            };
+
+            match (n, s, l) {
+                (Some(n), Some(s), Some(l))
+                    if n > 0 && (0.0..=1.0).contains(&s) && (0.0..=1.0).contains(&l) =>
+                {
+                    SourceArg::GoldenRatio(n, s, l)
+                }
+                _ => {
+                    return Err(AppError::invalid_value(
+                        &format!("invalid golden palette {}", arg),
+                    ));
+                }
+            }
         } else {
-            let arg = args.bit_depth.unwrap();
-            let depths: Vec<_> = arg
-                .split(',')
-                .map(|n| n.parse().ok())
-                .collect();
+            let channels = match (args.red_bits, args.green_bits, args.blue_bits) {
+                (Some(r), Some(g), Some(b)) => Some((r, g, b)),
+                _ => None,
+            };
 
-            let (r, g, b) = match depths.as_slice() {
-                [] => (8, 8, 8),
+            let arg = match channels {
+                Some((r, g, b)) => format!("{},{},{}", r, g, b),
+                None => args.bit_depth.unwrap(),
+            };
+            let params: Vec<_> = arg.split(',').collect();
 
+            let depths = match params.as_slice() {
                 // Allocate bits from most to least perceptually important
-                [Some(d)] => ((d + 1) / 3, (d + 2) / 3, d / 3),
+                [d] | [d, "perceptual"] => {
+                    d.parse::<u32>().ok().map(|d| ((d + 1) / 3, d.div_ceil(3), d / 3))
+                }
 
-                [Some(r), Some(g), Some(b)] => (*r, *g, *b),
+                [d, "uniform"] => {
+                    d.parse::<u32>().ok().filter(|d| d % 3 == 0).map(|d| (d / 3, d / 3, d / 3))
+                }
 
-                _ => {
+                [r, g, b] => match (r.parse(), g.parse(), b.parse()) {
+                    (Ok(r), Ok(g), Ok(b)) => Some((r, g, b)),
+                    _ => None,
+                },
+
+                _ => None,
+            };
+
+            let (r, g, b) = match depths {
+                Some(depths) => depths,
+                None => {
                     return Err(AppError::invalid_value(
                         &format!("invalid bit depth {}", arg),
                     ));
@@ -253,20 +689,65 @@ impl Args {
                 ));
             }
 
-            SourceArg::AllRgb(r, g, b)
+            if let Some(n) = args.palette {
+                if n == 0 {
+                    return Err(AppError::invalid_value("palette must not be empty"));
+                }
+
+                SourceArg::Palette(r, g, b, n, args.palette_space)
+            } else if let Some(n) = args.sample {
+                if n == 0 {
+                    return Err(AppError::invalid_value("sample must not be empty"));
+                }
+
+                SourceArg::Sample(r, g, b, n)
+            } else {
+                SourceArg::AllRgb(r, g, b)
+            }
         };
 
-        let order = if args.random {
+        let deduplicate = args.deduplicate;
+
+        let order = if args.saturation_sort {
+            OrderArg::SaturationSort
+        } else if args.luminance_sort {
+            OrderArg::LuminanceSort
+        } else if args.random {
             OrderArg::Random
         } else if args.morton {
             OrderArg::Morton
+        } else if args.morton3d {
+            OrderArg::Morton3d
         } else if args.hilbert {
             OrderArg::Hilbert
+        } else if args.hilbert3d {
+            OrderArg::Hilbert3d
+        } else if args.moore {
+            OrderArg::Moore
+        } else if args.peano {
+            OrderArg::Peano
+        } else if args.diagonal {
+            OrderArg::Diagonal
+        } else if args.checkerboard {
+            OrderArg::Checkerboard
+        } else if args.auto_order {
+            OrderArg::Auto
+        } else if args.van_der_corput {
+            OrderArg::VanDerCorput
+        } else if args.source_position_order {
+            OrderArg::SourcePosition
+        } else if args.freq_hue {
+            OrderArg::FrequencyHue
         } else {
             OrderArg::HueSort
         };
 
-        let stripe = !args.no_stripe && order != OrderArg::Random;
+        let reverse = args.reverse;
+
+        let stripe = !args.no_stripe
+            && order != OrderArg::Random
+            && order != OrderArg::VanDerCorput
+            && order != OrderArg::FrequencyHue;
 
         let frontier = if let Some(target) = args.target {
             FrontierArg::Image(target)
@@ -274,36 +755,262 @@ impl Args {
             args.selection
         };
 
+        let connectivity = if args.four_connect {
+            Connectivity::Four
+        } else {
+            Connectivity::Eight
+        };
+
+        let wrap = args.wrap;
+        if wrap && frontier != FrontierArg::Min {
+            return Err(AppError::invalid_value("--wrap is only supported with --selection min"));
+        }
+
+        let tiles = if let Some(arg) = args.tile_size {
+            let size = arg
+                .split_once(',')
+                .and_then(|(w, h)| Some((w.parse().ok()?, h.parse().ok()?)))
+                .filter(|&(w, h)| w > 0 && h > 0);
+
+            match size {
+                Some(size) => Some((size, args.tile_seed_pattern.into())),
+                None => return Err(AppError::invalid_value(&format!("invalid tile size {}", arg))),
+            }
+        } else {
+            None
+        };
+        if tiles.is_some() && frontier != FrontierArg::Min {
+            return Err(AppError::invalid_value(
+                "--tile-size is only supported with --selection min",
+            ));
+        }
+
         let space = args.color_space;
 
+        let weights = if let Some(arg) = args.color_weights {
+            if space.is_cylindrical() {
+                return Err(AppError::invalid_value(&format!(
+                    "--color-weights is not supported in the {:?} color space",
+                    space,
+                )));
+            }
+
+            let weights: Vec<_> = arg
+                .split(',')
+                .map(|w| w.parse::<f64>().ok().filter(|w| w.is_finite() && *w >= 0.0))
+                .collect();
+
+            match weights.as_slice() {
+                [Some(w1), Some(w2), Some(w3)] if w1 + w2 + w3 > 0.0 => Some([*w1, *w2, *w3]),
+                _ => {
+                    return Err(AppError::invalid_value(
+                        &format!("invalid color weights {}", arg),
+                    ));
+                }
+            }
+        } else {
+            None
+        };
+
+        let peak_luminance = args.peak_luminance;
+        if let Some(nits) = peak_luminance {
+            if !(nits.is_finite() && nits > 0.0) {
+                return Err(AppError::invalid_value(&format!("invalid peak luminance {}", nits)));
+            }
+        }
+
         let width = args.width;
         let height = args.height;
         let x0 = args.x0;
         let y0 = args.y0;
+        if !x0.is_empty() && !y0.is_empty() && x0.len() != y0.len() {
+            return Err(AppError::invalid_value(
+                "-x and -y must be given the same number of times",
+            ));
+        }
+
+        if let (Some(width), Some(height)) = (width, height) {
+            check_dimensions(width, height)?;
+        }
+        if let Some(x) = x0.iter().find(|&&x| width.is_some_and(|w| x >= w)) {
+            return Err(AppError::invalid_value(
+                &format!("Initial x coordinate {} is out of bounds ({})", x, width.unwrap()),
+            ));
+        }
+        if let Some(y) = y0.iter().find(|&&y| height.is_some_and(|h| y >= h)) {
+            return Err(AppError::invalid_value(
+                &format!("Initial y coordinate {} is out of bounds ({})", y, height.unwrap()),
+            ));
+        }
+
+        let debug_pixel = if let Some(arg) = args.debug_pixel {
+            let pos = arg
+                .split_once(',')
+                .and_then(|(x, y)| Some((x.parse().ok()?, y.parse().ok()?)));
+
+            if pos.is_none() {
+                return Err(AppError::invalid_value(
+                    &format!("invalid debug pixel {}", arg),
+                ));
+            }
+
+            pos
+        } else {
+            None
+        };
 
-        let animate = args.animate;
+        let measure_rejection = args.measure_rejection;
 
-        let output = args.output;
+        let target_softness = args.target_softness.unwrap_or(0);
+
+        let rebuild_ratio = if args.no_rebuild {
+            None
+        } else {
+            let ratio = args.rebuild_ratio.unwrap_or(REBUILD_RATIO);
+            if !(ratio > 0.0 && ratio <= 1.0) {
+                return Err(AppError::invalid_value(
+                    &format!("rebuild ratio {} is not in (0, 1]", ratio),
+                ));
+            }
+            Some(ratio)
+        };
+
+        let animate = if let Some(path) = args.apng {
+            Some(AnimationArg::Apng(path, args.frame_delay))
+        } else if args.raw_output {
+            Some(AnimationArg::RawFrames)
+        } else if args.animate {
+            Some(AnimationArg::Frames)
+        } else {
+            None
+        };
+
+        let benchmark = args.benchmark_mode.map(|n| (n, args.benchmark_warmup));
+
+        let background = if let Some(arg) = args.background_color {
+            match rgb8_from_hex(&arg) {
+                Ok(color) => Some(color),
+                Err(err) => return Err(AppError::invalid_value(&err.to_string())),
+            }
+        } else {
+            None
+        };
+
+        let format = match (args.format, &args.output) {
+            (Some(format), _) => format,
+            (None, Some(path)) => OutputFormatArg::from_path(path),
+            (None, None) => OutputFormatArg::Png,
+        };
+
+        let mut output = args.output.unwrap_or_else(|| PathBuf::from("kd-forest"));
+        if output.extension().is_none() {
+            output.set_extension(format.extension());
+        }
+
+        let jpeg_quality = args.jpeg_quality;
+
+        let num_threads = if args.single_thread {
+            Some(1)
+        } else {
+            args.num_threads.map(usize::from)
+        };
 
         let seed = args.seed;
 
+        let verbose = args.verbose;
+
+        let progress_json = args.progress_json;
+
         Ok(Self {
             source,
+            deduplicate,
             order,
+            reverse,
             stripe,
             frontier,
+            connectivity,
+            wrap,
+            tiles,
             space,
+            weights,
+            peak_luminance,
             width,
             height,
             x0,
             y0,
+            debug_pixel,
+            measure_rejection,
+            target_softness,
+            rebuild_ratio,
             animate,
+            benchmark,
+            background,
             output,
+            format,
+            jpeg_quality,
+            num_threads,
             seed,
+            verbose,
+            progress_json,
         })
     }
 }
 
+/// How to report progress.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum ProgressReporter {
+    /// Update a status line on the terminal.
+    Terminal,
+    /// Print a line of JSON for each update.
+    Json,
+}
+
+impl ProgressReporter {
+    /// Estimate the remaining time in seconds, if possible.
+    fn eta(remaining: usize, rate: f64) -> Option<f64> {
+        if remaining == 0 {
+            Some(0.0)
+        } else if rate > 0.0 && rate.is_finite() {
+            Some(remaining as f64 / rate)
+        } else {
+            None
+        }
+    }
+
+    /// Format an estimated remaining time as HH:MM:SS.
+    fn format_eta(eta: Option<f64>) -> String {
+        match eta {
+            Some(eta) => {
+                let secs = eta.round() as u64;
+                format!("{:02}:{:02}:{:02}", secs / 3600, secs / 60 % 60, secs % 60)
+            }
+            None => "--:--:--".to_string(),
+        }
+    }
+
+    /// Format a progress update as JSON.
+    fn json(
+        space_name: &str,
+        percent: f64,
+        rate: f64,
+        frontier_len: usize,
+        elapsed: f64,
+        eta: Option<f64>,
+    ) -> String {
+        // Keep the output valid JSON even if no time has elapsed
+        let rate = if rate.is_finite() { rate } else { 0.0 };
+        let eta = eta.map_or("null".to_string(), |eta| format!("{:.3}", eta));
+
+        format!(
+            concat!(
+                r#"{{"color_space": "{}", "percent": {:.2}, "rate_px_per_s": {:.0}, "#,
+                r#""frontier_size": {}, "elapsed_s": {:.3}, "eta_s": {}}}"#,
+            ),
+            space_name, percent, rate, frontier_len, elapsed, eta,
+        )
+    }
+}
+
 /// The kd-forest application itself.
 #[derive(Debug)]
 struct App {
@@ -312,6 +1019,9 @@ struct App {
     width: Option<u32>,
     height: Option<u32>,
     start_time: Instant,
+    progress: ProgressReporter,
+    /// The name of the color space being painted in.
+    space_name: &'static str,
 }
 
 impl App {
@@ -321,6 +1031,11 @@ impl App {
         let width = args.width;
         let height = args.height;
         let start_time = Instant::now();
+        let progress = if args.progress_json {
+            ProgressReporter::Json
+        } else {
+            ProgressReporter::Terminal
+        };
 
         Self {
             args,
@@ -328,79 +1043,363 @@ impl App {
             width,
             height,
             start_time,
+            progress,
+            space_name: "",
         }
     }
 
+    /// Make the random number generator for the frontier.
+    ///
+    /// This is derived from the seed directly, rather than drawn from the main generator, so the
+    /// frontier makes the same choices no matter how much randomness went into picking the colors.
+    fn frontier_rng(&self) -> Pcg64 {
+        Pcg64::seed_from_u64(self.args.seed.wrapping_add(FRONTIER_SEED_OFFSET))
+    }
+
     fn run(&mut self) -> AppResult<()> {
+        if let Some(n) = self.args.num_threads {
+            rayon::ThreadPoolBuilder::new().num_threads(n).build_global()?;
+        }
+
+        if let Some(nits) = self.args.peak_luminance {
+            if let Err(peak) = set_peak_luminance(nits) {
+                let msg = format!("Peak luminance is already set to {} cd/m²", peak);
+                return Err(AppError::RuntimeError(msg.into()));
+            }
+        }
+
         let colors = match self.args.source {
             SourceArg::AllRgb(r, g, b) => {
                 let total = r + g + b;
-                self.width.get_or_insert(1u32 << ((total + 1) / 2));
+                self.width.get_or_insert(1u32 << total.div_ceil(2));
                 self.height.get_or_insert(1u32 << (total / 2));
                 self.get_colors(AllColors::new(r, g, b))
             }
             SourceArg::Image(ref path) => {
                 let img = image::open(path)?.into_rgb8();
-                self.width.get_or_insert(img.width());
-                self.height.get_or_insert(img.height());
-                self.get_colors(ImageColors::from(img))
+                let (width, height) = img.dimensions();
+                let resized = self.width.is_some_and(|w| w != width)
+                    || self.height.is_some_and(|h| h != height);
+                if resized {
+                    eprintln!(
+                        "Warning: overriding the {}x{} size of {}",
+                        width,
+                        height,
+                        path.display(),
+                    );
+                }
+                self.width.get_or_insert(width);
+                self.height.get_or_insert(height);
+
+                let source = ImageColors::from(img);
+                if self.args.deduplicate {
+                    self.get_positioned_colors(source.deduplicated())
+                } else {
+                    self.get_positioned_colors(source)
+                }
+            }
+            SourceArg::Palette(r, g, b, n, space) => {
+                let all = AllColors::new(r, g, b);
+                let palette = match space {
+                    ColorSpaceArg::Rgb => all.n_most_distinct::<RgbSpace>(n),
+                    ColorSpaceArg::Hsl => all.n_most_distinct::<HslSpace>(n),
+                    ColorSpaceArg::Hsv => all.n_most_distinct::<HsvSpace>(n),
+                    ColorSpaceArg::Lab => all.n_most_distinct::<LabSpace>(n),
+                    ColorSpaceArg::Lch => all.n_most_distinct::<LchSpace>(n),
+                    ColorSpaceArg::Din99 => all.n_most_distinct::<Din99Space>(n),
+                    ColorSpaceArg::Luv => all.n_most_distinct::<LuvSpace>(n),
+                    ColorSpaceArg::Oklab => all.n_most_distinct::<OklabSpace>(n),
+                    ColorSpaceArg::Oklch => all.n_most_distinct::<OklchSpace>(n),
+                    ColorSpaceArg::Jzazbz => all.n_most_distinct::<JzazbzSpace>(n),
+                    ColorSpaceArg::Ictcp => all.n_most_distinct::<IctcpSpace>(n),
+                };
+
+                let n = palette.len();
+                let width = (n as f64).sqrt().ceil() as u32;
+                self.width.get_or_insert(width);
+                self.height.get_or_insert((n as u32).div_ceil(width));
+                self.get_colors(PaletteColors::from(palette))
+            }
+            SourceArg::Sample(r, g, b, n) => {
+                let sample = order::sample_random(AllColors::new(r, g, b), n, &mut self.rng);
+
+                let n = sample.len();
+                let width = (n as f64).sqrt().ceil() as u32;
+                self.width.get_or_insert(width);
+                self.height.get_or_insert((n as u32).div_ceil(width));
+                self.get_colors(PaletteColors::from(sample))
+            }
+            SourceArg::PaletteFile(ref path) => {
+                // Distinct colors can't number more than 2^24, so there's no need to check the size
+                let palette = PaletteColors::from(image::open(path)?.into_rgb8());
+                if !palette.is_distinct() {
+                    return Err(AppError::invalid_value(
+                        &format!("palette {} has duplicate colors", path.display()),
+                    ));
+                }
+
+                let n = palette.dimensions()[0];
+                let width = (n as f64).sqrt().ceil() as u32;
+                self.width.get_or_insert(width);
+                self.height.get_or_insert((n as u32).div_ceil(width));
+                self.get_colors(palette)
+            }
+            SourceArg::Gradient(from, to, n) => {
+                let width = (n as f64).sqrt().ceil() as u32;
+                self.width.get_or_insert(width);
+                self.height.get_or_insert((n as u32).div_ceil(width));
+                self.get_colors(GradientColors::new(from, to, n))
             }
+            SourceArg::GoldenRatio(n, s, l) => {
+                let width = (n as f64).sqrt().ceil() as u32;
+                self.width.get_or_insert(width);
+                self.height.get_or_insert((n as u32).div_ceil(width));
+                self.get_colors(GoldenRatioColors::new(n, s, l))
+            }
+        };
+
+        // Stripe the colors lazily, rather than collecting them into a second Vec
+        let colors: Box<dyn ExactSizeIterator<Item = Rgb8> + '_> = if self.args.stripe {
+            Box::new(order::StripedIter::new(&colors))
+        } else {
+            Box::new(colors.iter().copied())
         };
 
         match self.args.space {
-            ColorSpaceArg::Rgb => self.paint::<RgbSpace>(colors),
-            ColorSpaceArg::Lab => self.paint::<LabSpace>(colors),
-            ColorSpaceArg::Luv => self.paint::<LuvSpace>(colors),
-            ColorSpaceArg::Oklab => self.paint::<OklabSpace>(colors),
+            ColorSpaceArg::Rgb => self.paint_in::<RgbSpace, _>(colors),
+            ColorSpaceArg::Hsl => self.paint_in::<HslSpace, _>(colors),
+            ColorSpaceArg::Hsv => self.paint_in::<HsvSpace, _>(colors),
+            ColorSpaceArg::Lab => self.paint_in::<LabSpace, _>(colors),
+            ColorSpaceArg::Lch => self.paint_in::<LchSpace, _>(colors),
+            ColorSpaceArg::Din99 => self.paint_in::<Din99Space, _>(colors),
+            ColorSpaceArg::Luv => self.paint_in::<LuvSpace, _>(colors),
+            ColorSpaceArg::Oklab => self.paint_in::<OklabSpace, _>(colors),
+            ColorSpaceArg::Oklch => self.paint_in::<OklchSpace, _>(colors),
+            ColorSpaceArg::Jzazbz => self.paint_in::<JzazbzSpace, _>(colors),
+            ColorSpaceArg::Ictcp => self.paint_in::<IctcpSpace, _>(colors),
+        }
+    }
+
+    fn get_positioned_colors<S: HasPositions>(&mut self, source: S) -> Vec<Rgb8> {
+        if self.args.order == OrderArg::SourcePosition {
+            self.reverse(order::by_source_position(source))
+        } else {
+            self.get_colors(source)
         }
     }
 
     fn get_colors<S: ColorSource>(&mut self, source: S) -> Vec<Rgb8> {
+        if self.args.verbose && self.args.order == OrderArg::Hilbert {
+            Self::print_hilbert_stats(&source);
+        }
+
         let colors = match self.args.order {
             OrderArg::HueSort => order::hue_sorted(source),
+            OrderArg::SaturationSort => order::saturation_sorted(source),
+            OrderArg::LuminanceSort => order::luminance_sorted(source),
             OrderArg::Random => order::shuffled(source, &mut self.rng),
             OrderArg::Morton => order::morton(source),
+            OrderArg::Morton3d => order::morton3d(source),
             OrderArg::Hilbert => order::hilbert(source),
+            OrderArg::Hilbert3d => order::hilbert3d(source),
+            OrderArg::Moore => order::moore(source),
+            OrderArg::Peano => order::peano(source),
+            OrderArg::Diagonal => order::diagonal(source),
+            OrderArg::Checkerboard => order::checkerboard(source),
+            OrderArg::Auto => order::best_curve_order(source),
+            OrderArg::VanDerCorput => order::van_der_corput(source),
+            OrderArg::SourcePosition => unreachable!("--source-position-order requires --input"),
+            OrderArg::FrequencyHue => order::by_frequency_then_hue(source),
         };
 
-        if self.args.stripe {
-            order::striped(colors)
+        self.reverse(colors)
+    }
+
+    fn reverse(&self, mut colors: Vec<Rgb8>) -> Vec<Rgb8> {
+        if self.args.reverse {
+            colors.reverse();
+        }
+        colors
+    }
+
+    fn print_hilbert_stats<S: ColorSource>(source: &S) {
+        let bits: Vec<_> = source
+            .dimensions()
+            .iter()
+            .map(|n| n.next_power_of_two().trailing_zeros())
+            .collect();
+
+        eprintln!("Hilbert curve path length: {}", hilbert_path_length(&bits));
+        for (k, distance) in hilbert_path_locality(&bits) {
+            eprintln!("Hilbert curve locality (k = {}): {:.3}", k, distance);
+        }
+    }
+
+    fn paint_in<C, I>(&mut self, colors: I) -> AppResult<()>
+    where
+        C: ColorSpace,
+        C::Value: PartialOrd<C::Distance>,
+        I: IntoIterator<Item = Rgb8>,
+        I::IntoIter: ExactSizeIterator,
+    {
+        if let Some(weights) = self.args.weights {
+            if COLOR_WEIGHTS.set(weights).is_err() && COLOR_WEIGHTS.get() != Some(&weights) {
+                let msg = format!("Color weights are already set to {:?}", COLOR_WEIGHTS.get());
+                return Err(AppError::RuntimeError(msg.into()));
+            }
+            self.paint::<WeightedSpace<C, CliWeights>, _>(colors)
         } else {
-            colors
+            self.paint::<C, _>(colors)
         }
     }
 
-    fn paint<C: ColorSpace>(&mut self, colors: Vec<Rgb8>) -> AppResult<()>
+    fn paint<C, I>(&mut self, colors: I) -> AppResult<()>
     where
+        C: ColorSpace,
         C::Value: PartialOrd<C::Distance>,
+        I: IntoIterator<Item = Rgb8>,
+        I::IntoIter: ExactSizeIterator,
     {
+        self.space_name = C::name();
+
         let width = self.width.unwrap();
         let height = self.height.unwrap();
-        let x0 = self.args.x0.unwrap_or(width / 2);
-        let y0 = self.args.y0.unwrap_or(height / 2);
+        check_dimensions(width, height)?;
 
-        if x0 >= width || y0 >= height {
-            return Err(AppError::invalid_value(
-                &format!("Initial pixel ({}, {}) is out of bounds ({}, {})", x0, y0, width, height),
-            ));
+        if let Some((tile_size, pattern)) = self.args.tiles {
+            return self.paint_tiled::<C, _>(colors, width, height, tile_size, pattern);
         }
 
+        let n = cmp::max(1, cmp::max(self.args.x0.len(), self.args.y0.len()));
+        let seeds: Vec<_> = (0..n)
+            .map(|i| {
+                let x0 = self.args.x0.get(i).copied().unwrap_or(width / 2);
+                let y0 = self.args.y0.get(i).copied().unwrap_or(height / 2);
+                (x0, y0)
+            })
+            .collect();
+
+        for &(x0, y0) in &seeds {
+            if x0 >= width || y0 >= height {
+                return Err(AppError::invalid_value(
+                    &format!("Initial pixel ({}, {}) is out of bounds ({}, {})", x0, y0, width, height),
+                ));
+            }
+        }
+
+        if let Some((x, y)) = self.args.debug_pixel {
+            if x >= width || y >= height {
+                return Err(AppError::invalid_value(
+                    &format!("Debug pixel ({}, {}) is out of bounds ({}, {})", x, y, width, height),
+                ));
+            }
+        }
+
+        let connectivity = self.args.connectivity;
+
         match &self.args.frontier {
             FrontierArg::Image(ref path) => {
-                let img = image::open(path)?.into_rgb8();
-                self.paint_on(colors, ImageFrontier::<C>::new(&img))
+                let img = image::open(path)?.into_rgba8();
+                let mut frontier = ImageFrontier::<C>::new(&img);
+                if self.args.measure_rejection {
+                    frontier.track_rejections();
+                }
+                frontier.set_softness(self.args.target_softness);
+                frontier.set_rebuild_ratio(self.args.rebuild_ratio);
+
+                self.paint_on(colors, &mut frontier)?;
+
+                if let Some(rate) = frontier.rejection_rate() {
+                    eprintln!("Rejection rate: {:.3} per pixel", rate);
+                }
+
+                Ok(())
+            }
+            FrontierArg::Min if self.args.wrap => {
+                let rng = self.frontier_rng();
+                let mut frontier =
+                    WrapFrontier::<C, _>::new(rng, width, height, seeds, connectivity);
+                self.paint_on(colors, &mut frontier)
             }
             FrontierArg::Min => {
-                let rng = Pcg64::from_rng(&mut self.rng)?;
-                self.paint_on(colors, MinFrontier::<C, _>::new(rng, width, height, x0, y0))
+                let rng = self.frontier_rng();
+                let mut frontier =
+                    MinFrontier::<C, _>::new(rng, width, height, seeds, connectivity);
+                self.paint_on(colors, &mut frontier)
             }
             FrontierArg::Mean => {
-                self.paint_on(colors, MeanFrontier::<C>::new(width, height, x0, y0))
+                let mut frontier = MeanFrontier::<C>::new(width, height, seeds, connectivity);
+                self.paint_on(colors, &mut frontier)
+            }
+            FrontierArg::Median => {
+                let mut frontier = MedianFrontier::<C>::new(width, height, seeds, connectivity);
+                self.paint_on(colors, &mut frontier)
+            }
+            FrontierArg::Max => {
+                let rng = self.frontier_rng();
+                let mut frontier =
+                    MaxFrontier::<C, _>::new(rng, width, height, seeds, connectivity);
+                self.paint_on(colors, &mut frontier)
+            }
+            FrontierArg::Fountain => {
+                let mut frontier = FountainFrontier::<C>::new(width, height, seeds, connectivity);
+                self.paint_on(colors, &mut frontier)
             }
         }
     }
 
-    fn write_frame(image: &RgbaImage) -> AppResult<()> {
+    fn paint_tiled<C, I>(
+        &mut self,
+        colors: I,
+        width: u32,
+        height: u32,
+        tile_size: (u32, u32),
+        pattern: TileSeeds,
+    ) -> AppResult<()>
+    where
+        C: ColorSpace,
+        C::Value: PartialOrd<C::Distance>,
+        I: IntoIterator<Item = Rgb8>,
+        I::IntoIter: ExactSizeIterator,
+    {
+        let colors = colors.into_iter();
+        let size = cmp::min((width * height) as usize, colors.len());
+        eprintln!(
+            "Generating a {}x{} image ({} pixels) in {}, in {}x{} tiles on {} threads",
+            width,
+            height,
+            size,
+            self.space_name,
+            tile_size.0,
+            tile_size.1,
+            rayon::current_num_threads(),
+        );
+
+        let connectivity = self.args.connectivity;
+        let (mut image, stats) = paint_tiles::<C, _, _>(
+            width,
+            height,
+            tile_size,
+            colors,
+            pattern,
+            &mut self.frontier_rng(),
+            connectivity,
+        )?;
+
+        if stats.pixels_placed > 0 {
+            eprintln!("{}", stats);
+        }
+
+        if let Some(bg) = self.args.background {
+            for pixel in image.pixels_mut().filter(|p| p[3] == 0) {
+                *pixel = Rgba([bg[0], bg[1], bg[2], 255]);
+            }
+        }
+
+        Self::save_image(&image, &self.args.output, self.args.format, self.args.jpeg_quality)
+    }
+
+    fn frame_writer() -> AppResult<BufWriter<StdoutLock<'static>>> {
         let stdout = io::stdout();
         if stdout.is_terminal() {
             return Err(AppError::invalid_value(
@@ -408,45 +1407,118 @@ impl App {
             ));
         }
 
-        let writer = BufWriter::new(stdout.lock());
+        Ok(BufWriter::new(stdout.lock()))
+    }
+
+    fn write_frame_png<W: Write>(writer: W, image: &RgbaImage) -> AppResult<()> {
         let encoder = PngEncoder::new_with_quality(writer, CompressionType::Fast, FilterType::NoFilter);
         encoder.write_image(image, image.width(), image.height(), ColorType::Rgba8)?;
 
         Ok(())
     }
 
-    fn paint_on<F: Frontier>(&mut self, colors: Vec<Rgb8>, mut frontier: F) -> AppResult<()> {
+    fn write_frame_raw<W: Write>(mut writer: W, image: &RgbaImage) -> AppResult<()> {
+        writer.write_all(image.as_raw())?;
+        writer.flush()?;
+
+        Ok(())
+    }
+
+    /// Save an image, with the given quality if it's a JPEG.
+    fn save_image(
+        image: &RgbaImage,
+        path: &Path,
+        format: OutputFormatArg,
+        jpeg_quality: u8,
+    ) -> AppResult<()> {
+        match format {
+            OutputFormatArg::Png => image.save_with_format(path, ImageFormat::Png)?,
+            OutputFormatArg::Webp => {
+                let writer = BufWriter::new(File::create(path)?);
+                let encoder = WebPEncoder::new_lossless(writer);
+                encoder.write_image(image, image.width(), image.height(), ColorType::Rgba8)?;
+            }
+            OutputFormatArg::Jpeg => {
+                let image: RgbImage = image.convert();
+                let writer = BufWriter::new(File::create(path)?);
+                let encoder = JpegEncoder::new_with_quality(writer, jpeg_quality);
+                encoder.write_image(&image, image.width(), image.height(), ColorType::Rgb8)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Output an animation frame, or save it for later if it's part of an animated PNG.
+    fn add_frame(&self, frames: &mut Vec<RgbaImage>, image: &RgbaImage) -> AppResult<()> {
+        match self.args.animate {
+            Some(AnimationArg::Frames) => Self::write_frame_png(Self::frame_writer()?, image),
+            Some(AnimationArg::RawFrames) => Self::write_frame_raw(Self::frame_writer()?, image),
+            Some(AnimationArg::Apng(..)) => {
+                frames.push(image.clone());
+                Ok(())
+            }
+            None => Ok(()),
+        }
+    }
+
+    fn paint_on<F, I>(&mut self, colors: I, frontier: &mut F) -> AppResult<()>
+    where
+        F: Frontier,
+        I: IntoIterator<Item = Rgb8>,
+        I::IntoIter: ExactSizeIterator,
+    {
+        if let Some((n, warmup)) = self.args.benchmark {
+            println!("{}", Self::benchmark_on(colors, frontier, n, warmup));
+            if let Some(kib) = peak_rss_kib() {
+                eprintln!("Peak RSS: {} KiB", kib);
+            }
+            return Ok(());
+        }
+
         let width = frontier.width();
         let height = frontier.height();
-        let mut output = RgbaImage::new(width, height);
+        let mut output = match self.args.background {
+            Some(bg) => RgbaImage::from_pixel(width, height, Rgba([bg[0], bg[1], bg[2], 255])),
+            None => RgbaImage::new(width, height),
+        };
 
+        let colors = colors.into_iter();
         let size = cmp::min((width * height) as usize, colors.len());
-        eprintln!("Generating a {}x{} image ({} pixels)", width, height, size);
+        eprintln!(
+            "Generating a {}x{} image ({} pixels) in {}",
+            width, height, size, self.space_name,
+        );
 
-        if self.args.animate {
-            Self::write_frame(&output)?;
-        }
+        let mut frames = Vec::new();
+        self.add_frame(&mut frames, &output)?;
 
         let interval = cmp::max(width, height) as usize;
 
         let mut max_frontier = frontier.len();
 
-        for (i, color) in colors.into_iter().enumerate() {
-            let pos = frontier.place(color);
+        for (i, color) in colors.enumerate() {
+            let pos = match self.args.debug_pixel {
+                Some(debug_pos) => frontier.place_debug(color).map(|placement| {
+                    if placement.pos == debug_pos {
+                        eprint!("\n{}", placement);
+                    }
+                    placement.pos
+                }),
+                None => frontier.place(color),
+            };
             if pos.is_none() {
                 break;
             }
 
             let (x, y) = pos.unwrap();
-            let rgba = Rgba([color[0], color[1], color[2], 255]);
+            let rgba = Rgba([color[0], color[1], color[2], frontier.pixel_alpha(x, y)]);
             output.put_pixel(x, y, rgba);
 
             max_frontier = cmp::max(max_frontier, frontier.len());
 
             if (i + 1) % interval == 0 {
-                if self.args.animate {
-                    Self::write_frame(&output)?;
-                }
+                self.add_frame(&mut frames, &output)?;
 
                 if i + 1 < size {
                     self.print_progress(i + 1, size, frontier.len())?;
@@ -454,27 +1526,79 @@ impl App {
             }
         }
 
-        if self.args.animate && size % interval != 0 {
-            Self::write_frame(&output)?;
+        if !size.is_multiple_of(interval) {
+            self.add_frame(&mut frames, &output)?;
         }
 
         self.print_progress(size, size, max_frontier)?;
 
-        if !self.args.animate {
-            output.save(&self.args.output)?;
+        let stats = frontier.statistics();
+        if stats.pixels_placed > 0 {
+            eprintln!("{}", stats);
+        }
+
+        match &self.args.animate {
+            Some(AnimationArg::Apng(path, delay)) => {
+                write_apng(BufWriter::new(File::create(path)?), &frames, *delay)?;
+            }
+            Some(AnimationArg::Frames | AnimationArg::RawFrames) => {}
+            None => {
+                let (path, format) = (&self.args.output, self.args.format);
+                Self::save_image(&output, path, format, self.args.jpeg_quality)?;
+            }
         }
 
         Ok(())
     }
 
+    /// Place up to `n` colors, after `warmup` untimed ones, and return the rate in pixels/second.
+    fn benchmark_on<F, I>(colors: I, frontier: &mut F, n: usize, warmup: usize) -> f64
+    where
+        F: Frontier,
+        I: IntoIterator<Item = Rgb8>,
+    {
+        let mut colors = colors.into_iter();
+
+        for color in colors.by_ref().take(warmup) {
+            frontier.place(color);
+        }
+
+        let start = Instant::now();
+        let mut placed = 0;
+        for color in colors.take(n) {
+            if frontier.place(color).is_none() {
+                break;
+            }
+            placed += 1;
+        }
+
+        placed as f64 / start.elapsed().as_secs_f64()
+    }
+
     fn print_progress(&self, i: usize, size: usize, frontier_len: usize) -> io::Result<()> {
+        let progress = 100.0 * (i as f64) / (size as f64);
+        let elapsed = self.start_time.elapsed().as_secs_f64();
+        let mut rate = (i as f64) / elapsed;
+        let eta = ProgressReporter::eta(size - i, rate);
+
+        if self.progress == ProgressReporter::Json {
+            let json = ProgressReporter::json(
+                self.space_name,
+                progress,
+                rate,
+                frontier_len,
+                elapsed,
+                eta,
+            );
+            eprintln!("{}", json);
+            return Ok(());
+        }
+
         let mut term = match term::stderr() {
             Some(term) => term,
             None => return Ok(()),
         };
 
-        let progress = 100.0 * (i as f64) / (size as f64);
-        let mut rate = (i as f64) / self.start_time.elapsed().as_secs_f64();
         let mut unit = "px/s";
 
         if rate >= 10_000.0 {
@@ -503,8 +1627,14 @@ impl App {
 
         write!(
             term,
-            "{:>6.2}%  | {:4.0} {:>5}  | {}: {}{}",
-            progress, rate, unit, frontier_label, frontier_len, newline,
+            "{:>6.2}%  | {:4.0} {:>5}  | ETA {}  | {}: {}{}",
+            progress,
+            rate,
+            unit,
+            ProgressReporter::format_eta(eta),
+            frontier_label,
+            frontier_len,
+            newline,
         )
     }
 }
@@ -520,3 +1650,302 @@ fn main() {
         Err(e) => e.exit(),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use rand::Rng;
+
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[test]
+    fn test_webp_lossless() {
+        let image = RgbaImage::from_fn(16, 16, |x, y| {
+            Rgba([(16 * x) as u8, (16 * y) as u8, (x * y) as u8, 255 - (x + y) as u8])
+        });
+
+        let path = std::env::temp_dir().join(format!("kd-forest-{}.webp", std::process::id()));
+        App::save_image(&image, &path, OutputFormatArg::Webp, 90).unwrap();
+        let decoded = image::open(&path);
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(decoded.unwrap().into_rgba8(), image);
+    }
+
+    #[test]
+    fn test_jpeg() {
+        let image = RgbaImage::from_fn(8, 8, |x, y| {
+            Rgba([(32 * x) as u8, (32 * y) as u8, 128, 255])
+        });
+
+        let path = std::env::temp_dir().join(format!("kd-forest-{}.jpg", std::process::id()));
+        App::save_image(&image, &path, OutputFormatArg::Jpeg, 100).unwrap();
+        let decoded = image::open(&path);
+        std::fs::remove_file(&path).unwrap();
+
+        let decoded = decoded.unwrap().into_rgb8();
+        assert_eq!(decoded.dimensions(), (8, 8));
+        for (a, b) in image.pixels().zip(decoded.pixels()) {
+            for i in 0..3 {
+                assert!(a[i].abs_diff(b[i]) <= 5, "{:?} != {:?}", a, b);
+            }
+        }
+    }
+
+    #[test]
+    fn test_raw_frame() {
+        let image = RgbaImage::from_fn(4, 4, |x, y| Rgba([x as u8, y as u8, 0, 255]));
+
+        let mut raw = Vec::new();
+        App::write_frame_raw(&mut raw, &image).unwrap();
+        assert_eq!(raw.len(), 4 * 4 * 4);
+        assert_eq!(raw[4 * (4 + 2)..][..4], [2, 1, 0, 255]);
+    }
+
+    #[test]
+    fn test_progress_json() {
+        assert_eq!(
+            ProgressReporter::json(LabSpace::name(), 42.5, 12345.4, 678, 2.5, Some(3.0)),
+            concat!(
+                r#"{"color_space": "CIE L*a*b*", "percent": 42.50, "rate_px_per_s": 12345, "#,
+                r#""frontier_size": 678, "elapsed_s": 2.500, "eta_s": 3.000}"#,
+            )
+        );
+        assert_eq!(
+            ProgressReporter::json("sRGB", 0.0, f64::INFINITY, 0, 0.0, None),
+            concat!(
+                r#"{"color_space": "sRGB", "percent": 0.00, "rate_px_per_s": 0, "#,
+                r#""frontier_size": 0, "elapsed_s": 0.000, "eta_s": null}"#,
+            )
+        );
+    }
+
+    #[test]
+    fn test_eta() {
+        // Half of a 1000 pixel render done in 10 seconds
+        let eta = ProgressReporter::eta(500, 500.0 / 10.0);
+        assert_eq!(ProgressReporter::format_eta(eta), "00:00:10");
+
+        assert_eq!(ProgressReporter::format_eta(ProgressReporter::eta(0, 0.0)), "00:00:00");
+        assert_eq!(ProgressReporter::format_eta(ProgressReporter::eta(10, 0.0)), "--:--:--");
+        assert_eq!(ProgressReporter::format_eta(Some(3723.4)), "01:02:03");
+    }
+
+    #[test]
+    fn test_benchmark() {
+        let colors = order::hue_sorted(AllColors::new(4, 4, 4));
+        let rng = Pcg64::seed_from_u64(0);
+        let eight = Connectivity::Eight;
+        let mut frontier = MinFrontier::<LabSpace, _>::new(rng, 64, 64, vec![(32, 32)], eight);
+
+        let rate = App::benchmark_on(colors, &mut frontier, 1000, 100);
+        assert!(rate.is_finite() && rate > 0.0, "{}", rate);
+        assert_eq!(frontier.visited_pixels().count(), 1100);
+
+        if cfg!(target_os = "linux") {
+            assert!(peak_rss_kib().unwrap() > 0);
+        }
+    }
+
+    #[test]
+    fn test_color_weights() {
+        let weights = |args: &[&str]| parse_args(args).unwrap().weights;
+        assert_eq!(weights(&[]), None);
+        assert_eq!(weights(&["--color-weights", "2,1,1"]), Some([2.0, 1.0, 1.0]));
+        assert_eq!(weights(&["-c", "Oklab", "--color-weights", "2,1,1"]), Some([2.0, 1.0, 1.0]));
+        assert!(parse_args(&["--color-weights", "0,0,0"]).is_err());
+        assert!(parse_args(&["--color-weights", "1,2"]).is_err());
+
+        // The weights would apply to Cartesian coordinates, not the hue and chroma
+        for space in ["HSL", "HSV", "LCh", "OKLCh"] {
+            assert!(parse_args(&["-c", space, "--color-weights", "1,2,3"]).is_err());
+        }
+    }
+
+    #[test]
+    fn test_peak_luminance() {
+        assert_eq!(parse_args(&[]).unwrap().peak_luminance, None);
+        assert_eq!(parse_args(&["--peak-luminance", "100"]).unwrap().peak_luminance, Some(100.0));
+        assert!(parse_args(&["--peak-luminance", "0"]).is_err());
+        assert!(parse_args(&["--peak-luminance", "-203"]).is_err());
+        assert!(parse_args(&["--peak-luminance", "inf"]).is_err());
+    }
+
+    /// Parse some command line arguments.
+    fn parse_args(args: &[&str]) -> AppResult<Args> {
+        let cli = Cli::try_parse_from(["kd-forest"].iter().chain(args))?;
+        Args::from_cli(cli)
+    }
+
+    #[test]
+    fn test_bit_depth() {
+        let source = |args: &[&str]| parse_args(args).unwrap().source;
+
+        assert_eq!(source(&[]), SourceArg::AllRgb(8, 8, 8));
+        assert_eq!(source(&["-b19"]), SourceArg::AllRgb(6, 7, 6));
+        assert_eq!(source(&["-b19,perceptual"]), SourceArg::AllRgb(6, 7, 6));
+        assert_eq!(source(&["-b18,uniform"]), SourceArg::AllRgb(6, 6, 6));
+        assert_eq!(source(&["-b5,6,5"]), SourceArg::AllRgb(5, 6, 5));
+        assert!(parse_args(&["-b19,uniform"]).is_err());
+
+        let channels = ["--red-bits", "5", "--green-bits", "6", "--blue-bits", "5"];
+        assert_eq!(source(&channels), SourceArg::AllRgb(5, 6, 5));
+        assert_eq!(AllColors::new(5, 6, 5).total_count(), 65536);
+
+        assert!(parse_args(&["--red-bits", "5", "--green-bits", "6"]).is_err());
+        assert!(parse_args(&["-b24", "--red-bits", "5", "--green-bits", "6", "--blue-bits", "5"])
+            .is_err());
+    }
+
+    #[test]
+    fn test_reverse() {
+        let colors = |args: &[&str]| {
+            let mut args = args.to_vec();
+            args.extend(["-b9", "--no-stripe"]);
+            App::new(parse_args(&args).unwrap()).get_colors(AllColors::new(3, 3, 3))
+        };
+
+        let forward = colors(&["--hue-sort"]);
+        let mut reversed = colors(&["--hue-sort", "--reverse"]);
+        assert_eq!(forward.len(), 512);
+        assert_eq!(reversed.len(), forward.len());
+        assert_ne!(reversed, forward);
+        reversed.reverse();
+        assert_eq!(reversed, forward);
+
+        assert!(parse_args(&["-R"]).is_ok());
+        assert!(parse_args(&["-r", "-R"]).is_err());
+    }
+
+    #[test]
+    fn test_dimensions() {
+        let error = |args: &[&str]| match parse_args(args) {
+            Err(AppError::ArgError(err)) => err.to_string(),
+            _ => panic!("{:?} should be rejected", args),
+        };
+
+        assert!(error(&["-w1", "-h2147483648"]).contains("limit of 1073741824"));
+        assert!(error(&["-w0", "-h1"]).contains("empty"));
+        assert!(error(&["-w8", "-h8", "-x8"]).contains("out of bounds"));
+
+        assert!(parse_args(&["-w32768", "-h32768"]).is_ok());
+        assert!(parse_args(&["-w8", "-h8", "-x7", "-y7"]).is_ok());
+    }
+
+    #[test]
+    fn test_background_color() {
+        let path = std::env::temp_dir().join(format!("kd-forest-bg-{}.png", std::process::id()));
+        let path_arg = path.to_str().unwrap();
+        let args = parse_args(&["-w2", "-h2", "--background-color", "FF0000", "-o", path_arg]);
+        let mut app = App::new(args.unwrap());
+
+        let rng = Pcg64::seed_from_u64(0);
+        let seeds = vec![(0, 0)];
+        let mut frontier = MinFrontier::<LabSpace, _>::new(rng, 2, 2, seeds, Connectivity::Eight);
+        let colors = vec![Rgb8::from([0, 255, 0]), Rgb8::from([0, 0, 255])];
+        let result = app.paint_on(colors, &mut frontier);
+        let image = image::open(&path);
+        std::fs::remove_file(&path).unwrap();
+
+        result.unwrap();
+        let image = image.unwrap().into_rgba8();
+        assert_eq!(image.pixels().filter(|p| p.0 == [255, 0, 0, 255]).count(), 2);
+        assert_eq!(*image.get_pixel(0, 0), Rgba([0, 255, 0, 255]));
+
+        assert!(parse_args(&["--background-color", "red"]).is_err());
+    }
+
+    #[test]
+    fn test_tile_size() {
+        let args = parse_args(&["--tile-size", "512,256", "--tile-seed-pattern", "corners"]);
+        assert_eq!(args.unwrap().tiles, Some(((512, 256), TileSeeds::Corners)));
+        let args = parse_args(&["--tile-size", "64,64"]);
+        assert_eq!(args.unwrap().tiles, Some(((64, 64), TileSeeds::Center)));
+        assert_eq!(parse_args(&[]).unwrap().tiles, None);
+
+        assert!(parse_args(&["--tile-size", "0,64"]).is_err());
+        assert!(parse_args(&["--tile-size", "64"]).is_err());
+        assert!(parse_args(&["--tile-size", "64,64", "-l", "mean"]).is_err());
+        assert!(parse_args(&["--tile-size", "64,64", "--wrap"]).is_err());
+        assert!(parse_args(&["--tile-size", "64,64", "--benchmark-mode", "1000"]).is_err());
+        assert!(parse_args(&["--tile-size", "64,64", "--progress-json"]).is_err());
+        assert!(parse_args(&["--tile-seed-pattern", "random"]).is_err());
+    }
+
+    #[test]
+    fn test_num_threads() {
+        assert_eq!(parse_args(&["--num-threads", "4"]).unwrap().num_threads, Some(4));
+        assert_eq!(parse_args(&["--single-thread"]).unwrap().num_threads, Some(1));
+        assert_eq!(parse_args(&[]).unwrap().num_threads, None);
+
+        assert!(parse_args(&["--num-threads", "0"]).is_err());
+        assert!(parse_args(&["--num-threads", "1025"]).is_err());
+        assert!(parse_args(&["--num-threads", "2", "--single-thread"]).is_err());
+
+        // The output must not depend on the number of threads
+        let paint = |n| {
+            let pool = rayon::ThreadPoolBuilder::new().num_threads(n).build().unwrap();
+            let colors = order::hue_sorted(AllColors::new(4, 3, 3));
+            let mut rng = Pcg64::seed_from_u64(0);
+            let (seeds, eight) = (TileSeeds::Random, Connectivity::Eight);
+            let image = pool.install(|| {
+                paint_tiles::<LabSpace, _, _>(32, 32, (8, 8), colors, seeds, &mut rng, eight)
+            });
+            image.unwrap()
+        };
+        assert_eq!(paint(1), paint(2));
+    }
+
+    /// Generate a small image with the given extra arguments.
+    fn generate(extra: &[&str]) -> RgbaImage {
+        let id = GENERATED.fetch_add(1, Ordering::Relaxed);
+        let name = format!("kd-forest-gen-{}-{}.png", std::process::id(), id);
+        let path = std::env::temp_dir().join(name);
+
+        let mut args = vec!["-b12", "-o", path.to_str().unwrap()];
+        args.extend_from_slice(extra);
+        let result = App::new(parse_args(&args).unwrap()).run();
+        let image = image::open(&path);
+        std::fs::remove_file(&path).unwrap();
+
+        result.unwrap();
+        image.unwrap().into_rgba8()
+    }
+
+    /// A counter to give each generated image a unique name.
+    static GENERATED: AtomicUsize = AtomicUsize::new(0);
+
+    #[test]
+    fn test_seed() {
+        let mut app = App::new(parse_args(&["--seed", "42"]).unwrap());
+        let rng = app.frontier_rng();
+        let _: u64 = app.rng.gen();
+        assert_eq!(app.frontier_rng(), rng);
+
+        for extra in [&["--seed", "42"][..], &["--seed", "42", "--random"]] {
+            let first = generate(extra);
+            let second = generate(extra);
+            assert_eq!(first.get_pixel(0, 0), second.get_pixel(0, 0));
+            assert_eq!(first, second);
+        }
+    }
+
+    #[test]
+    fn test_output_format() {
+        assert_eq!(OutputFormatArg::from_path(Path::new("out.webp")), OutputFormatArg::Webp);
+        assert_eq!(OutputFormatArg::from_path(Path::new("out.WebP")), OutputFormatArg::Webp);
+        assert_eq!(OutputFormatArg::from_path(Path::new("out.png")), OutputFormatArg::Png);
+        assert_eq!(OutputFormatArg::from_path(Path::new("out")), OutputFormatArg::Png);
+        assert_eq!(OutputFormatArg::from_path(Path::new("out.jpg")), OutputFormatArg::Jpeg);
+        assert_eq!(OutputFormatArg::from_path(Path::new("out.JPEG")), OutputFormatArg::Jpeg);
+
+        let args = |args: &[&str]| parse_args(args).unwrap();
+        assert_eq!(args(&["-o", "out.jpg"]).format, OutputFormatArg::Jpeg);
+        assert_eq!(args(&["-o", "out.jpg"]).jpeg_quality, 90);
+        assert_eq!(args(&["--output-format", "jpeg"]).output, Path::new("kd-forest.jpg"));
+        assert_eq!(args(&["-o", "out.png", "--jpeg-quality", "50"]).format, OutputFormatArg::Png);
+        assert!(parse_args(&["--jpeg-quality", "0"]).is_err());
+        assert!(parse_args(&["--jpeg-quality", "101"]).is_err());
+    }
+}
@@ -0,0 +1,116 @@
+//! The [Peano curve](https://en.wikipedia.org/wiki/Peano_curve), generalized to any number of
+//! dimensions.
+//!
+//! The curve subdivides space into thirds along each axis.  Each base-3 digit of the index picks
+//! a third along one axis, and is reflected (d -> 2 - d) when the sum of the preceding digits for
+//! the other axes is odd, which makes consecutive points adjacent.
+
+/// Compute the corresponding point for a Peano index, on a grid with `3^digits` points per side.
+pub fn peano_point(index: usize, digits: u32, point: &mut [usize]) {
+    let dims = point.len() as u32;
+
+    // The sum of all the digits so far, and of the digits for each axis
+    let mut total = 0;
+    let mut sums = vec![0; point.len()];
+
+    for x in point.iter_mut() {
+        *x = 0;
+    }
+
+    let mut place = 3usize.pow(digits * dims);
+    for _ in 0..digits {
+        for (x, sum) in point.iter_mut().zip(sums.iter_mut()) {
+            place /= 3;
+            let digit = (index / place) % 3;
+            let reflected = if (total - *sum) % 2 == 1 { 2 - digit } else { digit };
+
+            *x = 3 * *x + reflected;
+            *sum += digit;
+            total += digit;
+        }
+    }
+}
+
+/// Compute the Peano index of a point, on a grid with `3^digits` points per side.
+///
+/// This is the inverse of [peano_point].
+pub fn peano_index(point: &[usize], digits: u32) -> usize {
+    let mut total = 0;
+    let mut sums = vec![0; point.len()];
+
+    let mut index = 0;
+    for i in (0..digits).rev() {
+        let place = 3usize.pow(i);
+        for (x, sum) in point.iter().zip(sums.iter_mut()) {
+            let reflected = (x / place) % 3;
+            let digit = if (total - *sum) % 2 == 1 { 2 - reflected } else { reflected };
+
+            index = 3 * index + digit;
+            *sum += digit;
+            total += digit;
+        }
+    }
+
+    index
+}
+
+/// The number of base-3 digits needed for a grid with `n` points per side.
+pub fn peano_digits(n: usize) -> u32 {
+    let mut digits = 0;
+    let mut size = 1;
+    while size < n {
+        size *= 3;
+        digits += 1;
+    }
+    digits
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_peano_point() {
+        let mut point = [0, 0];
+        let points: Vec<_> = (0..9)
+            .map(|i| {
+                peano_point(i, 1, &mut point);
+                (point[0], point[1])
+            })
+            .collect();
+
+        #[rustfmt::skip]
+        let expected = [
+            (0, 0), (0, 1), (0, 2),
+            (1, 2), (1, 1), (1, 0),
+            (2, 0), (2, 1), (2, 2),
+        ];
+        assert_eq!(points, expected);
+    }
+
+    #[test]
+    fn test_peano_continuous() {
+        for (dims, digits) in [(1, 3), (2, 3), (3, 2), (4, 1)] {
+            let size = 3usize.pow(dims * digits);
+            let mut prev = vec![0; dims as usize];
+            let mut point = vec![0; dims as usize];
+            for i in 0..size {
+                peano_point(i, digits, &mut point);
+                assert_eq!(peano_index(&point, digits), i);
+                if i > 0 {
+                    let steps: usize = point.iter().zip(&prev).map(|(x, y)| x.abs_diff(*y)).sum();
+                    assert_eq!(steps, 1, "{:?} -> {:?}", prev, point);
+                }
+                std::mem::swap(&mut prev, &mut point);
+            }
+        }
+    }
+
+    #[test]
+    fn test_peano_digits() {
+        assert_eq!(peano_digits(1), 0);
+        assert_eq!(peano_digits(3), 1);
+        assert_eq!(peano_digits(4), 2);
+        assert_eq!(peano_digits(256), 6);
+    }
+}
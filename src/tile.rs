@@ -0,0 +1,267 @@
+//! Tile-based parallel rendering.
+
+use crate::color::{ColorSpace, Rgb8};
+use crate::frontier::min::MinFrontier;
+use crate::frontier::{Connectivity, Frontier, Statistics};
+
+use image::{imageops, Rgba, RgbaImage};
+
+use rand::{Rng, SeedableRng};
+use rand_pcg::Pcg64;
+
+use rayon::prelude::*;
+
+use std::cmp::{Ordering, Reverse};
+use std::collections::BinaryHeap;
+
+/// Where to start painting each tile.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum TileSeeds {
+    /// The center of the tile.
+    #[default]
+    Center,
+    /// A random pixel in the tile.
+    Random,
+    /// All four corners of the tile.
+    Corners,
+}
+
+/// A rectangular tile of an image.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Tile {
+    /// The x coordinate of the tile's top-left corner.
+    pub x: u32,
+    /// The y coordinate of the tile's top-left corner.
+    pub y: u32,
+    /// The width of the tile.
+    pub width: u32,
+    /// The height of the tile.
+    pub height: u32,
+}
+
+impl Tile {
+    /// The number of pixels in this tile.
+    pub fn area(&self) -> usize {
+        (self.width as usize) * (self.height as usize)
+    }
+
+    /// Pick the starting pixels for this tile, relative to its top-left corner.
+    pub fn seeds<R: Rng>(&self, pattern: TileSeeds, rng: &mut R) -> Vec<(u32, u32)> {
+        let (w, h) = (self.width, self.height);
+        match pattern {
+            TileSeeds::Center => vec![(w / 2, h / 2)],
+            TileSeeds::Random => vec![(rng.gen_range(0..w), rng.gen_range(0..h))],
+            TileSeeds::Corners => vec![(0, 0), (w - 1, 0), (0, h - 1), (w - 1, h - 1)],
+        }
+    }
+}
+
+/// Divide an image into tiles of (at most) the given size, in row-major order.
+pub fn tiles(width: u32, height: u32, tile_width: u32, tile_height: u32) -> Vec<Tile> {
+    let mut tiles = Vec::new();
+    for y in (0..height).step_by(tile_height as usize) {
+        for x in (0..width).step_by(tile_width as usize) {
+            tiles.push(Tile {
+                x,
+                y,
+                width: tile_width.min(width - x),
+                height: tile_height.min(height - y),
+            });
+        }
+    }
+    tiles
+}
+
+/// The position of the next color dealt to a tile, for merging the tiles' shares.
+#[derive(Debug, Eq, PartialEq)]
+struct Deal {
+    /// The numerator of the position, 2 * (colors dealt so far) + 1.
+    num: u64,
+    /// The denominator of the position, 2 * (area of the tile).
+    denom: u64,
+    /// The index of the tile.
+    tile: usize,
+}
+
+impl Ord for Deal {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (self.num * other.denom)
+            .cmp(&(other.num * self.denom))
+            .then_with(|| self.tile.cmp(&other.tile))
+    }
+}
+
+impl PartialOrd for Deal {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Deal an ordered list of colors out to some tiles.
+///
+/// Each tile gets a share of the colors in proportion to its area, spread evenly throughout the
+/// list, so every tile sees the whole range of the color order.
+pub fn partition<I: IntoIterator<Item = Rgb8>>(colors: I, tiles: &[Tile]) -> Vec<Vec<Rgb8>> {
+    let mut shares: Vec<_> = tiles.iter().map(|t| Vec::with_capacity(t.area())).collect();
+
+    let mut heap: BinaryHeap<_> = tiles
+        .iter()
+        .enumerate()
+        .filter(|(_, t)| t.area() > 0)
+        .map(|(i, t)| Reverse(Deal { num: 1, denom: 2 * t.area() as u64, tile: i }))
+        .collect();
+
+    for color in colors {
+        let mut deal = match heap.pop() {
+            Some(Reverse(deal)) => deal,
+            None => break,
+        };
+        shares[deal.tile].push(color);
+        deal.num += 2;
+        heap.push(Reverse(deal));
+    }
+
+    shares
+}
+
+/// Paint a single tile on its own [MinFrontier], returning the image and the frontier's
+/// statistics.
+pub fn paint_tile<C: ColorSpace, R: Rng>(
+    tile: &Tile,
+    colors: Vec<Rgb8>,
+    rng: R,
+    seeds: Vec<(u32, u32)>,
+    connectivity: Connectivity,
+) -> (RgbaImage, Statistics)
+where
+    C::Value: PartialOrd<C::Distance>,
+{
+    let mut frontier = MinFrontier::<C, _>::new(rng, tile.width, tile.height, seeds, connectivity);
+    let mut image = RgbaImage::new(tile.width, tile.height);
+
+    for color in colors {
+        match frontier.place(color) {
+            Some((x, y)) => image.put_pixel(x, y, Rgba([color[0], color[1], color[2], 255])),
+            None => break,
+        }
+    }
+
+    (image, frontier.statistics())
+}
+
+/// Paint an image as independent tiles, in parallel.
+///
+/// Each tile grows from its own seeds with no knowledge of its neighbors, so the seams between
+/// tiles are visible.  That's the price of painting them all at once.
+///
+/// Returns the image, and the statistics of all the tiles combined.
+pub fn paint_tiles<C: ColorSpace, I: IntoIterator<Item = Rgb8>, R: Rng>(
+    width: u32,
+    height: u32,
+    tile_size: (u32, u32),
+    colors: I,
+    pattern: TileSeeds,
+    rng: &mut R,
+    connectivity: Connectivity,
+) -> Result<(RgbaImage, Statistics), rand::Error>
+where
+    C::Value: PartialOrd<C::Distance>,
+{
+    let tiles = tiles(width, height, tile_size.0, tile_size.1);
+    let shares = partition(colors, &tiles);
+
+    // Draw all the randomness up front, so the result doesn't depend on scheduling
+    let mut jobs = Vec::with_capacity(tiles.len());
+    for (tile, colors) in tiles.into_iter().zip(shares) {
+        let seeds = tile.seeds(pattern, rng);
+        let tile_rng = Pcg64::from_rng(&mut *rng)?;
+        jobs.push((tile, colors, tile_rng, seeds));
+    }
+
+    let painted: Vec<_> = jobs
+        .into_par_iter()
+        .map(|(tile, colors, rng, seeds)| {
+            let (image, stats) = paint_tile::<C, _>(&tile, colors, rng, seeds, connectivity);
+            (tile, image, stats)
+        })
+        .collect();
+
+    let mut image = RgbaImage::new(width, height);
+    let mut stats = Statistics::default();
+    for (tile, painted, tile_stats) in painted {
+        imageops::replace(&mut image, &painted, tile.x.into(), tile.y.into());
+        stats.merge(&tile_stats);
+    }
+
+    Ok((image, stats))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::color::source::AllColors;
+    use crate::color::{order, LabSpace};
+
+    #[test]
+    fn test_tiles() {
+        let tiles = tiles(10, 5, 4, 4);
+        assert_eq!(tiles.len(), 6);
+        assert_eq!(tiles[2], Tile { x: 8, y: 0, width: 2, height: 4 });
+        assert_eq!(tiles[5], Tile { x: 8, y: 4, width: 2, height: 1 });
+        assert_eq!(tiles.iter().map(Tile::area).sum::<usize>(), 50);
+    }
+
+    #[test]
+    fn test_partition() {
+        let tiles = tiles(12, 4, 8, 4);
+        let mut colors = order::hue_sorted(AllColors::new(2, 2, 2));
+        colors.truncate(48);
+        let shares = partition(colors.clone(), &tiles);
+        assert_eq!(shares[0].len(), 32);
+        assert_eq!(shares[1].len(), 16);
+
+        // Both tiles get colors from the whole order
+        let index = |c: &Rgb8| colors.iter().position(|d| d == c).unwrap();
+        assert_eq!(index(&shares[0][0]), 0);
+        assert_eq!(index(&shares[1][0]), 1);
+        assert_eq!(index(shares[0].last().unwrap()), 47);
+        assert_eq!(index(shares[1].last().unwrap()), 46);
+    }
+
+    #[test]
+    fn test_paint_tiles() {
+        let eight = Connectivity::Eight;
+        let colors = order::hue_sorted(AllColors::new(3, 3, 2));
+        assert_eq!(colors.len(), 256);
+
+        for pattern in [TileSeeds::Center, TileSeeds::Random, TileSeeds::Corners] {
+            let mut rng = Pcg64::seed_from_u64(0);
+            let (image, stats) = paint_tiles::<LabSpace, _, _>(
+                16,
+                16,
+                (8, 16),
+                colors.clone(),
+                pattern,
+                &mut rng,
+                eight,
+            )
+            .unwrap();
+            assert!(image.pixels().all(|p| p[3] == 255));
+            assert_eq!(stats.pixels_placed, 256);
+
+            // Each half should match painting that tile on its own
+            let mut rng = Pcg64::seed_from_u64(0);
+            let tiles = tiles(16, 16, 8, 16);
+            assert_eq!(tiles.len(), 2);
+            for (tile, colors) in tiles.iter().zip(partition(colors.clone(), &tiles)) {
+                let seeds = tile.seeds(pattern, &mut rng);
+                let tile_rng = Pcg64::from_rng(&mut rng).unwrap();
+                let (alone, _) = paint_tile::<LabSpace, _>(tile, colors, tile_rng, seeds, eight);
+
+                let view = imageops::crop_imm(&image, tile.x, tile.y, tile.width, tile.height);
+                assert_eq!(view.to_image(), alone);
+            }
+        }
+    }
+}